@@ -0,0 +1,169 @@
+//! Rendering errors with their location, as annotated source or JSON
+
+use crate::error::{ErrorContext, SuperTomlError};
+use std::fs;
+use std::ops::Range;
+
+/// Line and column (both starting at 1) of a byte offset in `source`
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = source[line_start..offset].chars().count() + 1;
+    (line, column)
+}
+
+fn read_source(context: &ErrorContext) -> Option<String> {
+    context
+        .file
+        .as_deref()
+        .and_then(|file| fs::read_to_string(file).ok())
+}
+
+/// Render an error for the terminal
+///
+/// Errors with a known location show the file, line and column followed by
+/// the source line with the offending range underlined, then the key and
+/// the chain of tables being resolved.
+pub fn format_error(error: &SuperTomlError) -> String {
+    let Some(context) = error.context() else {
+        return format!("Error: {}", error);
+    };
+    let source = read_source(context);
+    format_error_with_source(error, context, source.as_deref())
+}
+
+fn format_error_with_source(
+    error: &SuperTomlError,
+    context: &ErrorContext,
+    source: Option<&str>,
+) -> String {
+    let mut lines = vec![format!("Error: {}", error.message())];
+
+    let snippet = match (source, &context.span) {
+        (Some(source), Some(span)) => Some(source_snippet(source, span)),
+        _ => None,
+    };
+
+    if let Some(file) = &context.file {
+        match (&snippet, source, &context.span) {
+            (Some(_), Some(source), Some(span)) => {
+                let (line, column) = line_column(source, span.start);
+                lines.push(format!("  --> {}:{}:{}", file, line, column));
+            }
+            _ => lines.push(format!("  --> {}", file)),
+        }
+    }
+
+    if let Some(snippet) = snippet {
+        lines.extend(snippet);
+    }
+
+    if let Some(key) = &context.key {
+        lines.push(format!("  = key: {}", key));
+    }
+    if !context.call_stack.is_empty() {
+        lines.push(format!(
+            "  = while resolving: {}",
+            context.call_stack.join(" -> ")
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// The source line containing the start of `span`, underlined up to the end
+/// of the span or of the line
+fn source_snippet(source: &str, span: &Range<usize>) -> Vec<String> {
+    let start = span.start.min(source.len());
+    let (line_number, _) = line_column(source, start);
+    let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = source[start..]
+        .find('\n')
+        .map(|i| start + i)
+        .unwrap_or(source.len());
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    let end = span.end.clamp(start, line_end);
+    let offset = source[line_start..start].chars().count();
+    let width = source[start..end].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    vec![
+        format!("{} |", gutter),
+        format!("{} | {}", line_number, line),
+        format!("{} | {}{}", gutter, " ".repeat(offset), "^".repeat(width)),
+    ]
+}
+
+/// Describe an error as JSON, including its location when known
+pub fn error_to_json(error: &SuperTomlError) -> serde_json::Value {
+    let mut json = serde_json::json!({ "message": error.message() });
+
+    if let Some(context) = error.context() {
+        json["file"] = serde_json::json!(context.file);
+        json["call_stack"] = serde_json::json!(context.call_stack);
+        json["table"] = serde_json::json!(context.call_stack.last());
+        json["key"] = serde_json::json!(context.key);
+
+        if let Some(span) = &context.span {
+            json["span"] = serde_json::json!({ "start": span.start, "end": span.end });
+            if let Some(source) = read_source(context) {
+                let (line, column) = line_column(&source, span.start);
+                json["line"] = serde_json::json!(line);
+                json["column"] = serde_json::json!(column);
+            }
+        }
+    }
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_error_with_source() {
+        let source = "[app]\nname = \"x\"\nurl = \"{{ bad }\"\n";
+        let start = source.find("url").unwrap();
+        let error = SuperTomlError::PluginError {
+            plugin_name: "templating".to_string(),
+            error: "Template error: unexpected end".to_string(),
+        }
+        .with_context(|context| {
+            context.file = Some("app.toml".to_string());
+            context.call_stack = vec!["prod".to_string(), "app".to_string()];
+            context.key = Some("url".to_string());
+            context.span = Some(start..start + 15);
+        });
+
+        let context = error.context().unwrap().clone();
+        assert_eq!(
+            format_error_with_source(&error, &context, Some(source)),
+            "Error: Plugin 'templating' error: Template error: unexpected end
+  --> app.toml:3:1
+  |
+3 | url = \"{{ bad }\"
+  | ^^^^^^^^^^^^^^^
+  = key: url
+  = while resolving: prod -> app"
+        );
+    }
+
+    #[test]
+    fn test_format_error_without_context() {
+        let error = SuperTomlError::TableNotFound("missing".to_string());
+        assert_eq!(format_error(&error), "Error: Table 'missing' not found");
+    }
+
+    #[test]
+    fn test_line_column() {
+        let source = "a = 1\nbé = 2\n";
+        assert_eq!(line_column(source, 0), (1, 1));
+        assert_eq!(line_column(source, 6), (2, 1));
+        assert_eq!(line_column(source, source.find('=').unwrap()), (1, 3));
+        assert_eq!(line_column(source, source.rfind('=').unwrap()), (2, 4));
+    }
+}
@@ -1,13 +1,130 @@
+use crate::loader::FileFormat;
+use crate::resolver::CallFrame;
+use std::ops::Range;
+
+/// Where an error happened: the file, the tables being resolved, the key
+/// and the byte range in the file's source
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorContext {
+    pub file: Option<String>,
+    /// Tables being resolved when the error happened, outermost first
+    pub call_stack: Vec<String>,
+    pub key: Option<String>,
+    pub span: Option<Range<usize>>,
+}
+
 #[derive(Debug)]
 pub enum SuperTomlError {
     FileRead(std::io::Error),
     TomlParse(toml::de::Error),
+    /// A file in another format could not be read as TOML values
+    FileParse {
+        format: FileFormat,
+        error: String,
+    },
     TableNotFound(String),
     InvalidTableType(String),
-    CycleDetected(String),
-    PluginDeserialization { plugin_name: String, error: String },
-    PluginError { plugin_name: String, error: String },
+    InvalidTablePath {
+        path: String,
+        reason: String,
+    },
+    KeyNotFound {
+        key: String,
+        table: String,
+    },
+    /// A table was reached again while it was being resolved. `cycle` runs
+    /// from the first visit of `table` to the repeated one
+    CycleDetected {
+        table: String,
+        cycle: Vec<CallFrame>,
+    },
+    /// A command run for an import could not be started or failed. `status`
+    /// is `None` when it did not exit normally
+    CommandFailed {
+        command: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+    InvalidOverride {
+        arg: String,
+        reason: String,
+    },
+    PluginDeserialization {
+        plugin_name: String,
+        error: String,
+    },
+    PluginError {
+        plugin_name: String,
+        error: String,
+    },
     SerializationError(String),
+    InvalidOutputs(String),
+    OutputWrite {
+        path: String,
+        error: std::io::Error,
+    },
+    /// Another error together with where it happened
+    Located {
+        error: Box<SuperTomlError>,
+        context: ErrorContext,
+    },
+}
+
+impl SuperTomlError {
+    /// The underlying error, without location information
+    pub fn root(&self) -> &SuperTomlError {
+        match self {
+            SuperTomlError::Located { error, .. } => error.root(),
+            other => other,
+        }
+    }
+
+    /// Where the error happened, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            SuperTomlError::Located { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// A one-line description of the underlying error
+    ///
+    /// TOML parse errors include their own source excerpt when displayed;
+    /// this keeps only the message so the location can be shown separately.
+    pub fn message(&self) -> String {
+        match self.root() {
+            SuperTomlError::TomlParse(e) => format!("Failed to parse TOML: {}", e.message().trim()),
+            other => other.to_string(),
+        }
+    }
+
+    /// Add or update location information
+    pub fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        let (error, mut context) = match self {
+            SuperTomlError::Located { error, context } => (error, context),
+            other => (Box::new(other), ErrorContext::default()),
+        };
+        update(&mut context);
+        SuperTomlError::Located { error, context }
+    }
+
+    /// Record the key being processed, unless a key is already known
+    pub fn with_key(self, key: &str) -> Self {
+        self.with_context(|context| {
+            context.key.get_or_insert_with(|| key.to_string());
+        })
+    }
+
+    /// Replace the underlying error, keeping any location information
+    pub fn map_root(self, f: impl FnOnce(SuperTomlError) -> SuperTomlError) -> Self {
+        match self {
+            SuperTomlError::Located { error, context } => SuperTomlError::Located {
+                error: Box::new(error.map_root(f)),
+                context,
+            },
+            other => f(other),
+        }
+    }
 }
 
 impl std::fmt::Display for SuperTomlError {
@@ -15,10 +132,45 @@ impl std::fmt::Display for SuperTomlError {
         match self {
             SuperTomlError::FileRead(e) => write!(f, "Failed to read file: {}", e),
             SuperTomlError::TomlParse(e) => write!(f, "Failed to parse TOML: {}", e),
+            SuperTomlError::FileParse { format, error } => {
+                write!(
+                    f,
+                    "Failed to parse {}: {}",
+                    format.to_string().to_uppercase(),
+                    error
+                )
+            }
             SuperTomlError::TableNotFound(name) => write!(f, "Table '{}' not found", name),
             SuperTomlError::InvalidTableType(name) => write!(f, "Item '{}' is not a table", name),
-            SuperTomlError::CycleDetected(table) => {
-                write!(f, "Cycle detected when processing table '{}'", table)
+            SuperTomlError::InvalidTablePath { path, reason } => {
+                write!(f, "Invalid table path '{}': {}", path, reason)
+            }
+            SuperTomlError::KeyNotFound { key, table } => {
+                write!(f, "Key '{}' not found in resolved table '{}'", key, table)
+            }
+            SuperTomlError::CycleDetected { table, cycle } => {
+                write!(f, "Cycle detected when processing table '{}'", table)?;
+                if !cycle.is_empty() {
+                    write!(f, ": {}", format_cycle(cycle))?;
+                }
+                Ok(())
+            }
+            SuperTomlError::CommandFailed {
+                command,
+                status,
+                stderr,
+            } => {
+                write!(f, "Command '{}' failed", command)?;
+                if let Some(status) = status {
+                    write!(f, " with exit code {}", status)?;
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+            SuperTomlError::InvalidOverride { arg, reason } => {
+                write!(f, "Invalid override '{}': {}", arg, reason)
             }
             SuperTomlError::PluginDeserialization { plugin_name, error } => {
                 write!(
@@ -33,8 +185,69 @@ impl std::fmt::Display for SuperTomlError {
             SuperTomlError::SerializationError(error) => {
                 write!(f, "Serialization error: {}", error)
             }
+            SuperTomlError::InvalidOutputs(error) => write!(f, "Invalid _.outputs: {}", error),
+            SuperTomlError::OutputWrite { path, error } => {
+                write!(f, "Failed to write '{}': {}", path, error)
+            }
+            SuperTomlError::Located { error, .. } => write!(f, "{}", error),
         }
     }
 }
 
 impl std::error::Error for SuperTomlError {}
+
+/// Describe a cycle as `a -> b (_.before) -> a (_.after)`, naming the
+/// directive behind each edge and the file of any table outside the first
+/// table's file
+fn format_cycle(cycle: &[CallFrame]) -> String {
+    let home = cycle.first().and_then(|frame| frame.file.as_ref());
+    cycle
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let mut step = match &frame.file {
+                Some(file) if Some(file) != home => format!("{} [{}]", file, frame.table),
+                _ => frame.table.clone(),
+            };
+            if index > 0 {
+                if let Some(via) = &frame.via {
+                    step.push_str(&format!(" (_.{})", via));
+                }
+            }
+            step
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(table: &str, file: &str, via: Option<&str>) -> CallFrame {
+        CallFrame {
+            table: table.to_string(),
+            file: Some(file.to_string()),
+            via: via.map(|v| v.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_cycle_display_across_files() {
+        let error = SuperTomlError::CycleDetected {
+            table: "app".to_string(),
+            cycle: vec![
+                frame("app", "app.toml", None),
+                frame("shared", "app.toml", Some("before")),
+                frame("tools", "tools.toml", Some("import")),
+                frame("app", "app.toml", Some("after")),
+            ],
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Cycle detected when processing table 'app': app -> shared (_.before) -> \
+             tools.toml [tools] (_.import) -> app (_.after)"
+        );
+    }
+}
@@ -1,6 +1,35 @@
 use crate::error::SuperTomlError;
 use crate::loader::TomlTable;
+use serde::Deserialize;
 use std::collections::HashMap;
+use strum::{Display, EnumString};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Toml,
+    Json,
+    Dotenv,
+    Exports,
+    Tfvars,
+    Yaml,
+}
+
+/// Format resolved values in the given output format
+pub fn format_values(
+    values: &HashMap<String, toml::Value>,
+    format: OutputFormat,
+) -> Result<String, SuperTomlError> {
+    match format {
+        OutputFormat::Toml => format_as_toml(values),
+        OutputFormat::Json => format_as_json(values),
+        OutputFormat::Dotenv => format_as_dotenv(values),
+        OutputFormat::Exports => format_as_exports(values),
+        OutputFormat::Tfvars => format_as_tfvars(values),
+        OutputFormat::Yaml => format_as_yaml(values),
+    }
+}
 
 fn sorted_keys(values: &HashMap<String, toml::Value>) -> Vec<&String> {
     let mut keys: Vec<&String> = values.keys().collect();
@@ -27,8 +56,16 @@ pub fn format_as_json(values: &HashMap<String, toml::Value>) -> Result<String, S
 pub fn format_as_dotenv(values: &HashMap<String, toml::Value>) -> Result<String, SuperTomlError> {
     let lines: Vec<String> = sorted_keys(values)
         .into_iter()
-        .map(|key| format!("{}={}", key, value_to_string(&values[key])))
-        .collect();
+        .map(|key| {
+            let value = dotenv_quote(&value_to_string(&values[key])).map_err(|reason| {
+                SuperTomlError::SerializationError(format!(
+                    "value of '{}' cannot be written to a dotenv file: {}",
+                    key, reason
+                ))
+            })?;
+            Ok(format!("{}={}", key, value))
+        })
+        .collect::<Result<_, SuperTomlError>>()?;
     Ok(lines.join("\n"))
 }
 
@@ -37,23 +74,77 @@ pub fn format_as_exports(values: &HashMap<String, toml::Value>) -> Result<String
         .into_iter()
         .map(|key| {
             format!(
-                "export \"{}={}\"",
-                key,
-                value_to_exports_string(&values[key])
+                "export {}",
+                shell_quote(&format!("{}={}", key, value_to_string(&values[key])))
             )
         })
         .collect();
     Ok(lines.join("\n"))
 }
 
+/// Convert values into environment variables for a child process
+///
+/// Values are converted with the same rules as the dotenv output, without the
+/// quoting, and each name is prefixed with `prefix`.
+pub fn format_as_env_vars(
+    values: &HashMap<String, toml::Value>,
+    prefix: &str,
+) -> Result<Vec<(String, String)>, SuperTomlError> {
+    sorted_keys(values)
+        .into_iter()
+        .map(|key| {
+            let name = format!("{}{}", prefix, key);
+            if name.is_empty() || name.contains('=') || name.contains('\0') {
+                return Err(SuperTomlError::SerializationError(format!(
+                    "Key '{}' is not a valid environment variable name",
+                    name
+                )));
+            }
+            let value = value_to_string(&values[key]);
+            if value.contains('\0') {
+                return Err(SuperTomlError::SerializationError(format!(
+                    "Value of '{}' contains a NUL byte and cannot be passed in the environment",
+                    name
+                )));
+            }
+            Ok((name, value))
+        })
+        .collect()
+}
+
 pub fn format_as_tfvars(values: &HashMap<String, toml::Value>) -> Result<String, SuperTomlError> {
-    let lines: Vec<String> = sorted_keys(values)
+    let lines = sorted_keys(values)
         .into_iter()
-        .map(|key| format!("{} = {}", key, value_to_tfvars_string(&values[key])))
-        .collect();
+        .map(|key| {
+            if !is_hcl_identifier(key) {
+                return Err(SuperTomlError::SerializationError(format!(
+                    "Key '{}' is not a valid Terraform variable name",
+                    key
+                )));
+            }
+
+            let value = match &values[key] {
+                toml::Value::String(s) => hcl_heredoc(s).unwrap_or_else(|| hcl_string(s)),
+                other => value_to_tfvars_string(other),
+            };
+            Ok(format!("{} = {}", key, value))
+        })
+        .collect::<Result<Vec<String>, SuperTomlError>>()?;
     Ok(lines.join("\n"))
 }
 
+pub fn format_as_yaml(values: &HashMap<String, toml::Value>) -> Result<String, SuperTomlError> {
+    if values.is_empty() {
+        return Ok("{}".to_string());
+    }
+
+    let mut output = String::new();
+    for key in sorted_keys(values) {
+        write_yaml_entry(&mut output, 0, key, &values[key]);
+    }
+    Ok(output.trim_end().to_string())
+}
+
 fn resolved_values_to_json_value(values: &HashMap<String, toml::Value>) -> serde_json::Value {
     let mut json_map = serde_json::Map::new();
     for key in sorted_keys(values) {
@@ -62,7 +153,7 @@ fn resolved_values_to_json_value(values: &HashMap<String, toml::Value>) -> serde
     serde_json::Value::Object(json_map)
 }
 
-fn toml_value_to_json(value: &toml::Value) -> serde_json::Value {
+pub(crate) fn toml_value_to_json(value: &toml::Value) -> serde_json::Value {
     match value {
         toml::Value::String(s) => serde_json::Value::String(s.clone()),
         toml::Value::Integer(i) => serde_json::Value::Number((*i).into()),
@@ -98,29 +189,66 @@ fn value_to_string(value: &toml::Value) -> String {
     }
 }
 
-fn value_to_exports_string(value: &toml::Value) -> String {
-    match value {
-        toml::Value::String(s) => s.clone(),
-        toml::Value::Integer(i) => i.to_string(),
-        toml::Value::Float(f) => f.to_string(),
-        toml::Value::Boolean(b) => b.to_string(),
-        toml::Value::Datetime(dt) => dt.to_string(),
-        toml::Value::Array(_) | toml::Value::Table(_) => {
-            // For exports, we need to escape double quotes in JSON for shell safety
-            let json_str = serde_json::to_string(&toml_value_to_json(value))
-                .unwrap_or_else(|_| "null".to_string());
-            json_str.replace('"', "\\\"")
+/// Quote a string for POSIX shells using single quotes
+///
+/// Nothing is special inside single quotes, so the result is safe to `eval`
+/// for any input. Embedded single quotes are written as `'\''`.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Quote a value for a dotenv file as read by docker compose and python-dotenv
+///
+/// Simple values are left bare. Values without backslashes are single-quoted,
+/// which both parsers take literally apart from `\'`, including across lines.
+/// Other values are double-quoted with the backslash escapes both understand.
+/// python-dotenv expands `${...}` however a value is quoted, and the two
+/// disagree on `$` inside double quotes, so values needing both are errors.
+fn dotenv_quote(s: &str) -> Result<String, String> {
+    let is_plain = |c: char| c.is_alphanumeric() || "_-.,:/@%+=".contains(c);
+    if s.chars().all(is_plain) {
+        return Ok(s.to_string());
+    }
+
+    if s.contains("${") {
+        return Err("python-dotenv would expand '${' in it".to_string());
+    }
+
+    if !s.contains('\\') {
+        return Ok(format!("'{}'", s.replace('\'', "\\'")));
+    }
+
+    if s.contains('$') {
+        return Err(
+            "it has both '$' and a backslash, which docker compose and python-dotenv read \
+             differently"
+                .to_string(),
+        );
+    }
+
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
         }
     }
+    quoted.push('"');
+    Ok(quoted)
 }
 
 fn value_to_tfvars_string(value: &toml::Value) -> String {
     match value {
-        toml::Value::String(s) => format!("\"{}\"", s),
+        toml::Value::String(s) => hcl_string(s),
         toml::Value::Integer(i) => i.to_string(),
         toml::Value::Float(f) => f.to_string(),
         toml::Value::Boolean(b) => b.to_string(),
-        toml::Value::Datetime(dt) => format!("\"{}\"", dt),
+        toml::Value::Datetime(dt) => hcl_string(&dt.to_string()),
         toml::Value::Array(arr) => {
             let items: Vec<String> = arr.iter().map(value_to_tfvars_string).collect();
             format!("[{}]", items.join(", "))
@@ -130,9 +258,222 @@ fn value_to_tfvars_string(value: &toml::Value) -> String {
             let mut keys: Vec<&String> = table.keys().collect();
             keys.sort();
             for key in keys {
-                pairs.push(format!("{} = {}", key, value_to_tfvars_string(&table[key])));
+                let hcl_key = if is_hcl_identifier(key) && !key.contains('-') {
+                    key.clone()
+                } else {
+                    hcl_string(key)
+                };
+                pairs.push(format!(
+                    "{} = {}",
+                    hcl_key,
+                    value_to_tfvars_string(&table[key])
+                ));
             }
             format!("{{{}}}", pairs.join(", "))
         }
     }
 }
+
+fn is_hcl_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Escape template sequences so Terraform treats `${` and `%{` literally
+fn hcl_escape_templates(s: &str) -> String {
+    s.replace("${", "$${").replace("%{", "%%{")
+}
+
+fn hcl_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in hcl_escape_templates(s).chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Render a multiline string as a heredoc when it can be represented exactly
+///
+/// Heredocs always end with a newline and cannot contain escapes, so strings
+/// without a trailing newline or with other control characters stay quoted.
+/// `<<-EOT` strips common indentation, so fully indented text uses `<<EOT`.
+fn hcl_heredoc(s: &str) -> Option<String> {
+    let body = s.strip_suffix('\n')?;
+    if !body.contains('\n') || s.chars().any(|c| c.is_control() && c != '\n' && c != '\t') {
+        return None;
+    }
+
+    let lines: Vec<&str> = body.split('\n').collect();
+    let mut delimiter = "EOT".to_string();
+    let mut counter = 0;
+    while lines.iter().any(|line| line.trim() == delimiter) {
+        counter += 1;
+        delimiter = format!("EOT{}", counter);
+    }
+
+    let fully_indented = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.starts_with([' ', '\t']));
+    let marker = if fully_indented { "<<" } else { "<<-" };
+
+    Some(format!(
+        "{}{}\n{}\n{}",
+        marker,
+        delimiter,
+        hcl_escape_templates(body),
+        delimiter
+    ))
+}
+
+fn write_yaml_entry(output: &mut String, indent: usize, key: &str, value: &toml::Value) {
+    let prefix = format!("{}{}:", " ".repeat(indent), yaml_string(key));
+    match value {
+        toml::Value::Table(table) if !table.is_empty() => {
+            output.push_str(&prefix);
+            output.push('\n');
+            for (k, v) in table {
+                write_yaml_entry(output, indent + 2, k, v);
+            }
+        }
+        toml::Value::Array(arr) if !arr.is_empty() => {
+            output.push_str(&prefix);
+            output.push('\n');
+            for item in arr {
+                write_yaml_item(output, indent + 2, item);
+            }
+        }
+        _ => {
+            output.push_str(&format!("{} {}\n", prefix, yaml_scalar(value)));
+        }
+    }
+}
+
+fn write_yaml_item(output: &mut String, indent: usize, value: &toml::Value) {
+    let mut nested = String::new();
+    match value {
+        toml::Value::Table(table) if !table.is_empty() => {
+            for (k, v) in table {
+                write_yaml_entry(&mut nested, indent + 2, k, v);
+            }
+        }
+        toml::Value::Array(arr) if !arr.is_empty() => {
+            for item in arr {
+                write_yaml_item(&mut nested, indent + 2, item);
+            }
+        }
+        _ => {
+            output.push_str(&format!("{}- {}\n", " ".repeat(indent), yaml_scalar(value)));
+            return;
+        }
+    }
+
+    // The first nested line shares the line with the sequence indicator
+    output.push_str(&" ".repeat(indent));
+    output.push_str("- ");
+    output.push_str(&nested[indent + 2..]);
+}
+
+fn yaml_scalar(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => yaml_string(s),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => {
+            if f.is_nan() {
+                ".nan".to_string()
+            } else if f.is_infinite() {
+                if *f > 0.0 { ".inf" } else { "-.inf" }.to_string()
+            } else {
+                let s = f.to_string();
+                if s.contains(['.', 'e', 'E']) {
+                    s
+                } else {
+                    format!("{}.0", s)
+                }
+            }
+        }
+        toml::Value::Boolean(b) => b.to_string(),
+        // Datetimes are emitted as strings, matching the JSON output
+        toml::Value::Datetime(dt) => yaml_string(&dt.to_string()),
+        toml::Value::Array(_) => "[]".to_string(),
+        toml::Value::Table(_) => "{}".to_string(),
+    }
+}
+
+/// Emit a string as a plain scalar when no YAML 1.1 or 1.2 parser could read
+/// it as anything else, otherwise as a double-quoted scalar
+fn yaml_string(s: &str) -> String {
+    if is_plain_yaml_safe(s) {
+        return s.to_string();
+    }
+
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if is_yaml_special_char(c) => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn is_plain_yaml_safe(s: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "y", "n", "yes", "no", "true", "false", "on", "off", "null", "~", ".inf", "-.inf", "+.inf",
+        ".nan", "<<", "=",
+    ];
+
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+
+    if RESERVED.contains(&s.to_lowercase().as_str()) {
+        return false;
+    }
+
+    // Anything that starts like a number, date or time is quoted
+    let second = s.chars().nth(1);
+    if first.is_ascii_digit()
+        || (matches!(first, '-' | '+' | '.') && second.is_some_and(|c| c.is_ascii_digit()))
+    {
+        return false;
+    }
+
+    if "-?:,[]{}#&*!|>'\"%@`".contains(first) {
+        return false;
+    }
+
+    if s.starts_with(' ') || s.ends_with(' ') || s.ends_with(':') {
+        return false;
+    }
+
+    if s.contains(": ") || s.contains(" #") {
+        return false;
+    }
+
+    !s.chars().any(is_yaml_special_char)
+}
+
+fn is_yaml_special_char(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{2028}' | '\u{2029}' | '\u{feff}')
+}
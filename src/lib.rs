@@ -1,12 +1,19 @@
+pub mod diagnostics;
 mod error;
 mod formatter;
+pub mod listing;
 pub mod loader;
+pub mod merge;
+pub mod overrides;
 pub mod plugins;
+pub mod provenance;
+pub mod render;
 mod resolver;
 pub mod utils;
 
-pub use error::SuperTomlError;
+pub use error::{ErrorContext, SuperTomlError};
 pub use formatter::{
-    format_as_dotenv, format_as_exports, format_as_json, format_as_tfvars, format_as_toml,
+    format_as_dotenv, format_as_env_vars, format_as_exports, format_as_json, format_as_tfvars,
+    format_as_toml, format_as_yaml, format_values, OutputFormat,
 };
-pub use resolver::{resolve_table_recursive, Plugin, Resolver};
+pub use resolver::{resolve_table_recursive, CallFrame, Plugin, Resolver};
@@ -0,0 +1,245 @@
+//! Discovery of the tables in a file and the directives they carry
+
+use crate::loader::{format_table_path, TomlTable};
+use crate::Plugin;
+use serde::Serialize;
+
+/// A table or file that a directive pulls in
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dependency {
+    pub directive: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl Dependency {
+    pub fn table(directive: &str, table: &str) -> Self {
+        Self {
+            directive: directive.to_string(),
+            table: Some(table.to_string()),
+            file: None,
+        }
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (&self.file, &self.table) {
+            (Some(file), Some(table)) => write!(f, "{} [{}]", file, table),
+            (Some(file), None) => write!(f, "{}", file),
+            (None, Some(table)) => write!(f, "{}", table),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A table found in a file, with its `_` directives and dependencies
+#[derive(Debug, Clone, Serialize)]
+pub struct TableInfo {
+    pub table: String,
+    pub directives: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// List every table in a TOML document, including nested tables
+///
+/// Directives are reported in processing order, `_.extends` followed by the
+/// plugins' directives, then any directives no registered plugin handles.
+/// Dependencies are reported by the plugin that owns each directive.
+pub fn list_tables(toml: &toml::Value, plugins: &[&dyn Plugin]) -> Vec<TableInfo> {
+    let mut tables = Vec::new();
+    if let Some(root) = toml.as_table() {
+        collect_tables(root, &mut Vec::new(), plugins, &mut tables);
+    }
+    tables
+}
+
+fn collect_tables(
+    table: &TomlTable,
+    path: &mut Vec<String>,
+    plugins: &[&dyn Plugin],
+    tables: &mut Vec<TableInfo>,
+) {
+    for (key, value) in table {
+        let Some(child) = value.as_table() else {
+            continue;
+        };
+        if key == "_" {
+            continue;
+        }
+
+        path.push(key.clone());
+        tables.push(describe_table(child, path, plugins));
+        collect_tables(child, path, plugins, tables);
+        path.pop();
+    }
+}
+
+fn describe_table(table: &TomlTable, path: &[String], plugins: &[&dyn Plugin]) -> TableInfo {
+    let mut directives = Vec::new();
+    let mut dependencies = Vec::new();
+
+    if let Some(directive_table) = table.get("_").and_then(|v| v.as_table()) {
+        // `_.extends` is handled by the resolver rather than a plugin
+        if let Some(config) = directive_table.get("extends") {
+            directives.push("extends".to_string());
+            let parents = match config {
+                toml::Value::Array(parents) => parents.iter().collect(),
+                parent => vec![parent],
+            };
+            dependencies.extend(
+                parents
+                    .into_iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|parent| Dependency::table("extends", parent)),
+            );
+        }
+
+        for plugin in plugins {
+            if let Some(config) = directive_table.get(plugin.name()) {
+                directives.push(plugin.name().to_string());
+                dependencies.extend(plugin.dependencies(config));
+            }
+        }
+
+        for name in directive_table.keys() {
+            if !directives.contains(name) {
+                directives.push(name.clone());
+            }
+        }
+    }
+
+    TableInfo {
+        table: format_table_path(path),
+        directives,
+        dependencies,
+    }
+}
+
+/// Render table information as indented text, one directive per line
+pub fn format_table_list(tables: &[TableInfo]) -> String {
+    let mut lines = Vec::new();
+    for info in tables {
+        lines.push(info.table.clone());
+        for directive in &info.directives {
+            let targets: Vec<String> = info
+                .dependencies
+                .iter()
+                .filter(|d| &d.directive == directive)
+                .map(|d| d.to_string())
+                .collect();
+            if targets.is_empty() {
+                lines.push(format!("  _.{}", directive));
+            } else {
+                lines.push(format!("  _.{}: {}", directive, targets.join(", ")));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugins() -> Vec<&'static dyn Plugin> {
+        vec![
+            &crate::plugins::BeforePlugin as &dyn Plugin,
+            &crate::plugins::ImportPlugin as &dyn Plugin,
+            &crate::plugins::TemplatingPlugin as &dyn Plugin,
+            &crate::plugins::AfterPlugin as &dyn Plugin,
+        ]
+    }
+
+    #[test]
+    fn test_list_tables() {
+        let toml: toml::Value = toml::from_str(
+            r#"
+[global]
+debug = false
+
+[services.api]
+_.after = ["final"]
+_.import = [{ file = "mise.toml", table = "tools" }]
+_.before = ["global"]
+_.custom = true
+port = 8080
+
+[final]
+name = "x"
+"#,
+        )
+        .unwrap();
+
+        let tables = list_tables(&toml, &plugins());
+        let names: Vec<&str> = tables.iter().map(|t| t.table.as_str()).collect();
+        assert_eq!(names, vec!["final", "global", "services", "services.api"]);
+
+        let api = &tables[3];
+        assert_eq!(api.directives, vec!["before", "import", "after", "custom"]);
+        assert_eq!(
+            api.dependencies,
+            vec![
+                Dependency::table("before", "global"),
+                Dependency {
+                    directive: "import".to_string(),
+                    table: Some("tools".to_string()),
+                    file: Some("mise.toml".to_string()),
+                },
+                Dependency::table("after", "final"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_extends() {
+        let toml: toml::Value = toml::from_str(
+            r#"
+[prod]
+_.before = ["secrets"]
+_.extends = ["defaults", "eu"]
+
+[staging]
+_.extends = "defaults"
+"#,
+        )
+        .unwrap();
+
+        let tables = list_tables(&toml, &plugins());
+        assert_eq!(tables[0].directives, vec!["extends", "before"]);
+        assert_eq!(
+            tables[0].dependencies,
+            vec![
+                Dependency::table("extends", "defaults"),
+                Dependency::table("extends", "eu"),
+                Dependency::table("before", "secrets"),
+            ]
+        );
+        assert_eq!(
+            tables[1].dependencies,
+            vec![Dependency::table("extends", "defaults")]
+        );
+    }
+
+    #[test]
+    fn test_format_table_list() {
+        let toml: toml::Value = toml::from_str(
+            r#"
+[app]
+_.before = ["base", "region"]
+_.templating = { typed = true }
+
+[base]
+"#,
+        )
+        .unwrap();
+
+        let output = format_table_list(&list_tables(&toml, &plugins()));
+        assert_eq!(
+            output,
+            "app\n  _.before: base, region\n  _.templating\nbase"
+        );
+    }
+}
@@ -1,10 +1,900 @@
 use crate::error::SuperTomlError;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use strum::{Display, EnumString};
 
 pub type TomlTable = toml::map::Map<String, toml::Value>;
 
+/// Formats that files can be loaded from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    Toml,
+    Json,
+    Yaml,
+    Dotenv,
+    Ini,
+}
+
+impl FileFormat {
+    /// Choose a format from a file's name
+    ///
+    /// `.env` and `.env.*` files are dotenv; unrecognised extensions are
+    /// read as TOML.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> FileFormat {
+        let path = path.as_ref();
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if file_name == ".env" || file_name.starts_with(".env.") {
+            return FileFormat::Dotenv;
+        }
+
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => FileFormat::Json,
+            Some("yaml" | "yml") => FileFormat::Yaml,
+            Some("env") => FileFormat::Dotenv,
+            Some("ini" | "cfg") => FileFormat::Ini,
+            _ => FileFormat::Toml,
+        }
+    }
+}
+
 pub fn load_toml_file<P: AsRef<Path>>(path: P) -> Result<toml::Value, SuperTomlError> {
+    load_toml_source(path).map(|(_, value)| value)
+}
+
+/// Load a TOML file, returning its source text along with the parsed value
+///
+/// Parse errors carry the file and the byte span of the problem.
+pub fn load_toml_source<P: AsRef<Path>>(path: P) -> Result<(String, toml::Value), SuperTomlError> {
+    let path = path.as_ref();
     let content = fs::read_to_string(path).map_err(SuperTomlError::FileRead)?;
-    content.parse().map_err(SuperTomlError::TomlParse)
+    let value = content.parse().map_err(|e: toml::de::Error| {
+        let span = e.span();
+        SuperTomlError::TomlParse(e).with_context(|context| {
+            context.file = Some(path.display().to_string());
+            context.span = span;
+        })
+    })?;
+    Ok((content, value))
+}
+
+/// Load a file in any supported format as a TOML value
+///
+/// The format is taken from the file's name unless given. Values TOML
+/// cannot represent, such as nulls and arrays mixing types, are errors
+/// naming where they are in the file.
+pub fn load_file_as_toml<P: AsRef<Path>>(
+    path: P,
+    format: Option<FileFormat>,
+) -> Result<toml::Value, SuperTomlError> {
+    let path = path.as_ref();
+    let format = format.unwrap_or_else(|| FileFormat::from_path(path));
+    if format == FileFormat::Toml {
+        return load_toml_file(path);
+    }
+
+    let content = fs::read_to_string(path).map_err(SuperTomlError::FileRead)?;
+    parse_as_toml(&content, format).map_err(|e| {
+        e.with_context(|context| {
+            context.file = Some(path.display().to_string());
+        })
+    })
+}
+
+/// Load a file as it was at a git revision, returning its source text along
+/// with the parsed value
+///
+/// The content is read from the object database of the repository holding
+/// `path`, so the revision must already be available locally. Errors name
+/// the file as `path@git_ref`.
+pub fn load_git_file_as_toml<P: AsRef<Path>>(
+    path: P,
+    git_ref: &str,
+    format: Option<FileFormat>,
+) -> Result<(String, toml::Value), SuperTomlError> {
+    let path = path.as_ref();
+    let format = format.unwrap_or_else(|| FileFormat::from_path(path));
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    // `./` makes the path relative to `dir` rather than the repository root
+    let object = format!("{}:./{}", git_ref, file_name);
+
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["cat-file", "blob", &object])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| SuperTomlError::CommandFailed {
+            command: format!("git cat-file blob {}", object),
+            status: None,
+            stderr: e.to_string(),
+        })?;
+    if !output.status.success() {
+        return Err(SuperTomlError::CommandFailed {
+            command: format!("git cat-file blob {}", object),
+            status: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_string(),
+        });
+    }
+
+    let file = format!("{}@{}", path.display(), git_ref);
+    let content = String::from_utf8(output.stdout).map_err(|_| {
+        SuperTomlError::FileParse {
+            format,
+            error: "the file is not valid UTF-8".to_string(),
+        }
+        .with_context(|context| context.file = Some(file.clone()))
+    })?;
+    let value = parse_as_toml(&content, format)
+        .map_err(|e| e.with_context(|context| context.file = Some(file.clone())))?;
+    Ok((content, value))
+}
+
+/// Parse text in the given format as a TOML value
+pub fn parse_as_toml(content: &str, format: FileFormat) -> Result<toml::Value, SuperTomlError> {
+    let parse_error = |error: String, span: Option<Range<usize>>| {
+        SuperTomlError::FileParse { format, error }.with_context(|context| context.span = span)
+    };
+
+    let value = match format {
+        FileFormat::Toml => content.parse().map_err(|e: toml::de::Error| {
+            let span = e.span();
+            SuperTomlError::TomlParse(e).with_context(|context| context.span = span)
+        })?,
+        FileFormat::Json => {
+            let json: serde_json::Value = serde_json::from_str(content).map_err(|e| {
+                let offset = line_column_offset(content, e.line(), e.column());
+                parse_error(e.to_string(), offset.map(|o| o..o + 1))
+            })?;
+            json_to_toml(&json, format)?
+        }
+        FileFormat::Yaml => {
+            let json: serde_json::Value = serde_yaml::from_str(content).map_err(|e| {
+                let span = e.location().map(|l| l.index()..l.index() + 1);
+                parse_error(e.to_string(), span)
+            })?;
+            json_to_toml(&json, format)?
+        }
+        FileFormat::Dotenv => parse_dotenv(content)
+            .map_err(|(error, offset)| parse_error(error, Some(offset..offset + 1)))?,
+        FileFormat::Ini => parse_ini(content)
+            .map_err(|(error, offset)| parse_error(error, Some(offset..offset + 1)))?,
+    };
+
+    check_homogeneous_arrays(&value, "")
+        .map_err(|error| SuperTomlError::FileParse { format, error })?;
+    Ok(value)
+}
+
+fn json_to_toml(
+    json: &serde_json::Value,
+    format: FileFormat,
+) -> Result<toml::Value, SuperTomlError> {
+    let value = crate::utils::json_value_to_toml(json)
+        .map_err(|error| SuperTomlError::FileParse { format, error })?;
+    match value {
+        toml::Value::Table(_) => Ok(value),
+        _ => Err(SuperTomlError::FileParse {
+            format,
+            error: "the top level is not a table".to_string(),
+        }),
+    }
+}
+
+/// Byte offset of a 1-based line and column, as reported by serde_json
+fn line_column_offset(content: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    Some((line_start + column.saturating_sub(1)).min(content.len()))
+}
+
+/// Reject arrays whose elements are not all of one type
+fn check_homogeneous_arrays(value: &toml::Value, path: &str) -> Result<(), String> {
+    match value {
+        toml::Value::Array(items) => {
+            if let Some(first) = items.first() {
+                if let Some(other) = items.iter().find(|v| v.type_str() != first.type_str()) {
+                    return Err(format!(
+                        "'{}' mixes {} and {} values, which TOML arrays cannot hold",
+                        path,
+                        first.type_str(),
+                        other.type_str()
+                    ));
+                }
+            }
+            for (index, item) in items.iter().enumerate() {
+                check_homogeneous_arrays(item, &format!("{}[{}]", path, index))?;
+            }
+            Ok(())
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                check_homogeneous_arrays(item, &child_path)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parse dotenv text into a table of strings
+///
+/// Lines are `KEY=value`, optionally prefixed with `export`. Values may be
+/// single-quoted (literal), double-quoted (with `\n`, `\t`, `\"`, `\\`
+/// escapes, and may span lines) or bare, where a ` #` starts a comment.
+/// Errors carry the byte offset of the offending line.
+fn parse_dotenv(content: &str) -> Result<toml::Value, (String, usize)> {
+    let mut table = TomlTable::new();
+    let mut offset = 0;
+    let mut rest = content;
+
+    while !rest.is_empty() {
+        let line_end = rest.find('\n').map(|i| i + 1).unwrap_or(rest.len());
+        let line = rest[..line_end].trim_end_matches(['\n', '\r']);
+        let line_offset = offset;
+        let mut consumed = line_end;
+
+        let trimmed = line.trim_start();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            let assignment = trimmed
+                .strip_prefix("export ")
+                .map(str::trim_start)
+                .unwrap_or(trimmed);
+            let (key, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| (format!("expected KEY=value, found '{}'", line), line_offset))?;
+            let key = key.trim();
+            if key.is_empty() || key.chars().any(char::is_whitespace) {
+                return Err((format!("invalid key '{}'", key), line_offset));
+            }
+
+            let value = value.trim_start();
+            let parsed = match value.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    // Quoted values may continue over several lines
+                    let value_offset = line.len() - value.len() + 1;
+                    let body = &rest[value_offset..];
+                    let close = find_closing_quote(body, quote).ok_or_else(|| {
+                        (
+                            format!("unterminated quoted value for '{}'", key),
+                            line_offset,
+                        )
+                    })?;
+                    let after = &body[close + 1..];
+                    let after_end = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+                    let trailing = after[..after_end].trim();
+                    if !trailing.is_empty() && !trailing.starts_with('#') {
+                        return Err((
+                            format!("unexpected text after quoted value for '{}'", key),
+                            line_offset,
+                        ));
+                    }
+                    consumed = value_offset + close + 1 + after_end;
+                    let raw = &body[..close];
+                    if quote == '"' {
+                        unescape_double_quoted(raw)
+                    } else {
+                        unescape_single_quoted(raw)
+                    }
+                }
+                _ => match value.find(" #") {
+                    Some(comment) => value[..comment].trim_end().to_string(),
+                    None => value.trim_end().to_string(),
+                },
+            };
+            table.insert(key.to_string(), toml::Value::String(parsed));
+        }
+
+        offset += consumed;
+        rest = &rest[consumed..];
+    }
+
+    Ok(toml::Value::Table(table))
+}
+
+/// Find the quote ending a quoted value; a backslash escapes the next
+/// character in either kind of quotes, as python-dotenv reads them
+fn find_closing_quote(body: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in body.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Single-quoted values only unescape `\'` and `\\`; other backslashes are
+/// kept
+fn unescape_single_quoted(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next @ ('\'' | '\\'))) => {
+                result.push(next);
+                chars.next();
+            }
+            (c, _) => result.push(c),
+        }
+    }
+    result
+}
+
+fn unescape_double_quoted(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Parse INI text into a table of sections
+///
+/// `[section]` headers start nested tables and keys before the first header
+/// go in the root table. Entries are `key = value` or `key: value`; lines
+/// starting with `;` or `#` are comments. Values are strings, with matching
+/// surrounding quotes removed.
+fn parse_ini(content: &str) -> Result<toml::Value, (String, usize)> {
+    let mut root = TomlTable::new();
+    let mut section: Option<String> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_offset = offset;
+        offset += line.len();
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| (format!("invalid section header '{}'", trimmed), line_offset))?;
+            match root
+                .entry(name.to_string())
+                .or_insert_with(|| toml::Value::Table(TomlTable::new()))
+            {
+                toml::Value::Table(_) => section = Some(name.to_string()),
+                _ => {
+                    return Err((
+                        format!("section '{}' has the same name as a key", name),
+                        line_offset,
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let (key, value) = trimmed.split_once(['=', ':']).ok_or_else(|| {
+            (
+                format!("expected key = value, found '{}'", trimmed),
+                line_offset,
+            )
+        })?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(("empty key".to_string(), line_offset));
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|v| v.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+
+        let table = match &section {
+            Some(name) => match root.get_mut(name) {
+                Some(toml::Value::Table(table)) => table,
+                _ => unreachable!("section tables are created with their header"),
+            },
+            None => &mut root,
+        };
+        table.insert(key.to_string(), toml::Value::String(value.to_string()));
+    }
+
+    Ok(toml::Value::Table(root))
+}
+
+/// Remove `.` components and fold `name/..` pairs in a path without
+/// touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Find the byte range of a key within a table, or of the table itself when
+/// `key_path` is empty, in TOML source text
+pub fn find_span(source: &str, table_path: &[String], key_path: &[String]) -> Option<Range<usize>> {
+    let document = toml_edit::ImDocument::parse(source).ok()?;
+    let mut table: &dyn toml_edit::TableLike = document.as_table();
+    let mut span = None;
+
+    for segment in table_path {
+        let (key, item) = table.get_key_value(segment)?;
+        span = match item {
+            toml_edit::Item::Table(t) => t.span().or_else(|| key.span()),
+            _ => key.span(),
+        };
+        table = item.as_table_like()?;
+    }
+
+    // Dotted keys such as `_.before` are reported from their first segment
+    // on the line of the last one, as a table may have several `_.` lines
+    let mut first = None;
+    for (index, segment) in key_path.iter().enumerate() {
+        let (key, item) = table.get_key_value(segment)?;
+        let key_span = key.span()?;
+        let first = *first.get_or_insert(key_span.start);
+        if index + 1 == key_path.len() {
+            let line_start = source[..key_span.start].rfind('\n').map_or(0, |i| i + 1);
+            let start = if first >= line_start {
+                first
+            } else {
+                let indent = source[line_start..key_span.start].len()
+                    - source[line_start..key_span.start].trim_start().len();
+                line_start + indent
+            };
+            let end = item.span().map(|s| s.end).unwrap_or(key_span.end);
+            return Some(start..end.max(start));
+        }
+        table = item.as_table_like()?;
+    }
+
+    // Point at the table header rather than the whole table
+    span.map(|span| {
+        let header_end = source[span.start..]
+            .find('\n')
+            .map(|i| span.start + i)
+            .unwrap_or(span.end);
+        span.start..header_end.min(span.end)
+    })
+}
+
+/// Split a dotted table path such as `services.api` into its key segments
+///
+/// Segments follow TOML key syntax: bare keys, `"basic"` quoted keys and
+/// `'literal'` quoted keys, separated by dots with optional surrounding
+/// whitespace.
+pub fn parse_table_path(path: &str) -> Result<Vec<String>, SuperTomlError> {
+    let invalid = |reason: &str| SuperTomlError::InvalidTablePath {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+            chars.next();
+        }
+
+        let segment = match chars.peek() {
+            Some('"') => {
+                chars.next();
+                let mut segment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => segment.push('"'),
+                            Some('\\') => segment.push('\\'),
+                            Some('n') => segment.push('\n'),
+                            Some('t') => segment.push('\t'),
+                            Some('r') => segment.push('\r'),
+                            _ => return Err(invalid("unsupported escape sequence")),
+                        },
+                        Some(c) => segment.push(c),
+                        None => return Err(invalid("unterminated quoted key")),
+                    }
+                }
+                segment
+            }
+            Some('\'') => {
+                chars.next();
+                let mut segment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => segment.push(c),
+                        None => return Err(invalid("unterminated quoted key")),
+                    }
+                }
+                segment
+            }
+            _ => {
+                let mut segment = String::new();
+                while let Some(c) = chars.peek().copied() {
+                    if !is_bare_key_char(c) {
+                        break;
+                    }
+                    segment.push(c);
+                    chars.next();
+                }
+                if segment.is_empty() {
+                    return Err(invalid("empty key"));
+                }
+                segment
+            }
+        };
+        segments.push(segment);
+
+        while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+            chars.next();
+        }
+
+        match chars.next() {
+            None => return Ok(segments),
+            Some('.') => continue,
+            Some(c) => return Err(invalid(&format!("unexpected character '{}'", c))),
+        }
+    }
+}
+
+/// Join key segments back into a canonical dotted path, quoting where needed
+pub fn format_table_path(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            if !segment.is_empty() && segment.chars().all(is_bare_key_char) {
+                segment.clone()
+            } else {
+                format!(
+                    "\"{}\"",
+                    segment
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                        .replace('\n', "\\n")
+                        .replace('\t', "\\t")
+                        .replace('\r', "\\r")
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Resolve a table path against a root table
+///
+/// A top-level key that matches `path` exactly takes precedence, so existing
+/// table names containing dots or spaces keep working. Otherwise the path is
+/// parsed as dotted key segments and followed through nested tables. Returns
+/// the table along with its canonical path.
+pub fn find_table<'a>(
+    root: &'a TomlTable,
+    path: &str,
+) -> Result<(&'a TomlTable, String), SuperTomlError> {
+    let segments = match root.get(path) {
+        Some(_) => vec![path.to_string()],
+        None => parse_table_path(path)?,
+    };
+    let canonical = format_table_path(&segments);
+
+    let mut current = root;
+    for (index, segment) in segments.iter().enumerate() {
+        let walked = format_table_path(&segments[..=index]);
+        current = current
+            .get(segment)
+            .ok_or_else(|| SuperTomlError::TableNotFound(walked.clone()))?
+            .as_table()
+            .ok_or(SuperTomlError::InvalidTableType(walked))?;
+    }
+
+    Ok((current, canonical))
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table_path_bare() {
+        assert_eq!(
+            parse_table_path("services.api.database").unwrap(),
+            vec!["services", "api", "database"]
+        );
+    }
+
+    #[test]
+    fn test_parse_table_path_quoted() {
+        assert_eq!(
+            parse_table_path(r#"servers . "eu.west" .'raw\key'"#).unwrap(),
+            vec!["servers", "eu.west", "raw\\key"]
+        );
+    }
+
+    #[test]
+    fn test_parse_table_path_invalid() {
+        assert!(parse_table_path("services..api").is_err());
+        assert!(parse_table_path("services.\"api").is_err());
+        assert!(parse_table_path("my table").is_err());
+    }
+
+    #[test]
+    fn test_format_table_path_round_trip() {
+        let segments = parse_table_path(r#"servers."eu.west".api"#).unwrap();
+        assert_eq!(format_table_path(&segments), r#"servers."eu.west".api"#);
+    }
+
+    #[test]
+    fn test_find_table_nested() {
+        let root: TomlTable = toml::from_str(
+            r#"
+[services.api]
+port = 8080
+
+["dotted.name"]
+value = 1
+"#,
+        )
+        .unwrap();
+
+        let (table, path) = find_table(&root, "services . api").unwrap();
+        assert_eq!(path, "services.api");
+        assert_eq!(table["port"].as_integer(), Some(8080));
+
+        let (table, path) = find_table(&root, "dotted.name").unwrap();
+        assert_eq!(path, "\"dotted.name\"");
+        assert_eq!(table["value"].as_integer(), Some(1));
+
+        assert!(matches!(
+            find_table(&root, "services.web"),
+            Err(SuperTomlError::TableNotFound(name)) if name == "services.web"
+        ));
+        assert!(matches!(
+            find_table(&root, "services.api.port"),
+            Err(SuperTomlError::InvalidTableType(name)) if name == "services.api.port"
+        ));
+    }
+
+    #[test]
+    fn test_find_span() {
+        let source = r#"[global]
+port = 80
+
+[services.api]
+_.before = ["global"]
+url = "http://{{ host }}"
+
+[app]
+_.before = ["global"]
+_.import = [{ file = "missing.toml" }]
+  _.extends = "global"
+
+[split._]
+after = ["global"]
+"#;
+        let table = |path: &str| parse_table_path(path).unwrap();
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+
+        let span = find_span(source, &table("services.api"), &keys(&["url"])).unwrap();
+        assert_eq!(&source[span], r#"url = "http://{{ host }}""#);
+
+        let span = find_span(source, &table("services.api"), &keys(&["_", "before"])).unwrap();
+        assert_eq!(&source[span], r#"_.before = ["global"]"#);
+
+        let span = find_span(source, &table("global"), &[]).unwrap();
+        assert_eq!(&source[span], "[global]");
+
+        let span = find_span(source, &table("app"), &keys(&["_", "import"])).unwrap();
+        assert_eq!(&source[span], r#"_.import = [{ file = "missing.toml" }]"#);
+
+        let span = find_span(source, &table("app"), &keys(&["_", "extends"])).unwrap();
+        assert_eq!(&source[span], r#"_.extends = "global""#);
+
+        let span = find_span(source, &table("split"), &keys(&["_", "after"])).unwrap();
+        assert_eq!(&source[span], r#"after = ["global"]"#);
+
+        assert_eq!(
+            find_span(source, &table("global"), &keys(&["missing"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load_toml_source_parse_error_location() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), "[a]\nx = = 1\n").unwrap();
+
+        let error = load_toml_source(file.path()).unwrap_err();
+        assert!(matches!(error.root(), SuperTomlError::TomlParse(_)));
+        let context = error.context().unwrap();
+        assert_eq!(context.file.as_deref(), file.path().to_str());
+        assert_eq!(context.span, Some(8..9));
+    }
+
+    #[test]
+    fn test_file_format_from_path() {
+        assert_eq!(FileFormat::from_path("package.json"), FileFormat::Json);
+        assert_eq!(FileFormat::from_path("values.YML"), FileFormat::Yaml);
+        assert_eq!(FileFormat::from_path("conf/.env"), FileFormat::Dotenv);
+        assert_eq!(FileFormat::from_path(".env.local"), FileFormat::Dotenv);
+        assert_eq!(FileFormat::from_path("php.ini"), FileFormat::Ini);
+        assert_eq!(FileFormat::from_path("Cargo.toml"), FileFormat::Toml);
+        assert_eq!(FileFormat::from_path("config"), FileFormat::Toml);
+    }
+
+    #[test]
+    fn test_parse_json_and_yaml() {
+        let expected: toml::Value = toml::from_str(
+            r#"
+[server]
+port = 8080
+ratio = 0.5
+hosts = ["a", "b"]
+"#,
+        )
+        .unwrap();
+
+        let json = r#"{ "server": { "port": 8080, "ratio": 0.5, "hosts": ["a", "b"] } }"#;
+        assert_eq!(parse_as_toml(json, FileFormat::Json).unwrap(), expected);
+
+        let yaml = "server:\n  port: 8080\n  ratio: 0.5\n  hosts: [a, b]\n";
+        assert_eq!(parse_as_toml(yaml, FileFormat::Yaml).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_unrepresentable_values() {
+        let error = parse_as_toml(r#"{ "a": { "b": null } }"#, FileFormat::Json).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse JSON: 'a.b' is null, which TOML cannot represent"
+        );
+
+        let error = parse_as_toml("items: [1, two]\n", FileFormat::Yaml).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse YAML: 'items' mixes integer and string values, which TOML arrays \
+             cannot hold"
+        );
+
+        let error = parse_as_toml("[1, 2]", FileFormat::Json).unwrap_err();
+        assert!(error.to_string().contains("the top level is not a table"));
+    }
+
+    #[test]
+    fn test_parse_json_error_location() {
+        let error = parse_as_toml("{\n  \"a\": ,\n}", FileFormat::Json).unwrap_err();
+        assert!(matches!(error.root(), SuperTomlError::FileParse { .. }));
+        assert_eq!(error.context().unwrap().span, Some(9..10));
+    }
+
+    #[test]
+    fn test_parse_dotenv() {
+        let content = r#"# settings
+export HOST=localhost
+PORT = 8080 # trailing comment
+GREETING='$HOME \n stays'
+MESSAGE="line one\nsays \"hi\""
+MULTI="first
+second"
+EMPTY=
+"#;
+        let value = parse_as_toml(content, FileFormat::Dotenv).unwrap();
+        let table = value.as_table().unwrap();
+        assert_eq!(table["HOST"].as_str(), Some("localhost"));
+        assert_eq!(table["PORT"].as_str(), Some("8080"));
+        assert_eq!(table["GREETING"].as_str(), Some("$HOME \\n stays"));
+        assert_eq!(table["MESSAGE"].as_str(), Some("line one\nsays \"hi\""));
+        assert_eq!(table["MULTI"].as_str(), Some("first\nsecond"));
+        assert_eq!(table["EMPTY"].as_str(), Some(""));
+
+        let error = parse_as_toml("A=1\nB\n", FileFormat::Dotenv).unwrap_err();
+        assert_eq!(error.context().unwrap().span, Some(4..5));
+        assert!(parse_as_toml("A=\"open\n", FileFormat::Dotenv).is_err());
+
+        // Files written by the dotenv formatter read back unchanged
+        let values: std::collections::HashMap<String, toml::Value> = [
+            ("apostrophe", "it's here"),
+            ("quotes", "say \"hi\" and 'bye'"),
+            ("backslash", r"C:\temp\new"),
+            ("multiline", "line one\nit's line two"),
+            ("mixed", "a\\b\n'c' \"d\""),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), toml::Value::from(value)))
+        .collect();
+        let written = crate::formatter::format_as_dotenv(&values).unwrap();
+        let value = parse_as_toml(&written, FileFormat::Dotenv).unwrap();
+        let table = value.as_table().unwrap();
+        assert_eq!(table.len(), values.len());
+        for (key, expected) in &values {
+            assert_eq!(&table[key], expected, "{}", written);
+        }
+    }
+
+    #[test]
+    fn test_parse_ini() {
+        let content = r#"; comment
+name = root
+[database]
+host = db.example.com
+# another comment
+user: "admin"
+"#;
+        let value = parse_as_toml(content, FileFormat::Ini).unwrap();
+        let table = value.as_table().unwrap();
+        assert_eq!(table["name"].as_str(), Some("root"));
+        assert_eq!(table["database"]["host"].as_str(), Some("db.example.com"));
+        assert_eq!(table["database"]["user"].as_str(), Some("admin"));
+
+        assert!(parse_as_toml("[broken\n", FileFormat::Ini).is_err());
+        assert!(parse_as_toml("name = x\n[name]\n", FileFormat::Ini).is_err());
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("conf/./shared/../app.toml")),
+            PathBuf::from("conf/app.toml")
+        );
+        assert_eq!(
+            normalize_path(Path::new("conf/../../app.toml")),
+            PathBuf::from("../app.toml")
+        );
+        assert_eq!(
+            normalize_path(Path::new("/../etc/app.toml")),
+            PathBuf::from("/etc/app.toml")
+        );
+    }
 }
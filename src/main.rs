@@ -1,89 +1,462 @@
-use clap::{Parser, ValueEnum};
-use strum::{Display, EnumString};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use supertoml::overrides::{resolve_with_overrides, Override, OverrideMode};
+use supertoml::OutputFormat;
 
-#[derive(Clone, Debug, ValueEnum, Display, EnumString)]
-#[strum(serialize_all = "lowercase")]
-enum OutputFormat {
+#[derive(Clone, Debug, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ErrorFormat {
+    /// Messages with annotated source snippets
+    #[default]
+    Human,
+    /// One JSON object per error
+    Json,
+}
+
+/// Output formats, mapped to the library's `OutputFormat`
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputArg {
     Toml,
     Json,
     Dotenv,
     Exports,
     Tfvars,
+    Yaml,
 }
 
+impl From<OutputArg> for OutputFormat {
+    fn from(arg: OutputArg) -> Self {
+        match arg {
+            OutputArg::Toml => OutputFormat::Toml,
+            OutputArg::Json => OutputFormat::Json,
+            OutputArg::Dotenv => OutputFormat::Dotenv,
+            OutputArg::Exports => OutputFormat::Exports,
+            OutputArg::Tfvars => OutputFormat::Tfvars,
+            OutputArg::Yaml => OutputFormat::Yaml,
+        }
+    }
+}
+
+/// When --set values are applied, mapped to the library's `OverrideMode`
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SetMode {
+    /// Replace resolved values after all tables are processed
+    Override,
+    /// Provide starting values that templates can use and tables can replace
+    Seed,
+}
+
+impl From<SetMode> for OverrideMode {
+    fn from(mode: SetMode) -> Self {
+        match mode {
+            SetMode::Override => OverrideMode::Override,
+            SetMode::Seed => OverrideMode::Seed,
+        }
+    }
+}
+
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+static ALLOW_EXEC: OnceLock<bool> = OnceLock::new();
+
 #[derive(Parser)]
 #[command(name = "supertoml")]
 #[command(about = "A super TOML tool")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    file: String,
-    table: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    file: Option<String>,
+    /// Tables to extract; values from later tables override earlier ones
+    #[arg(required = true, num_args = 1..)]
+    tables: Vec<String>,
     #[arg(short, long, value_enum, default_value = "toml")]
-    output: OutputFormat,
+    output: OutputArg,
+    #[command(flatten)]
+    set: SetArgs,
+    /// How errors are reported
+    #[arg(long, value_enum, global = true, default_value = "human")]
+    error_format: ErrorFormat,
+    /// Allow imports to run commands
+    #[arg(long, global = true)]
+    allow_exec: bool,
+}
+
+#[derive(clap::Args)]
+struct SetArgs {
+    /// Set a value (key=value), parsed as a TOML literal; may be repeated
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+    /// Set a value to the contents of a file (key=path); may be repeated
+    #[arg(long = "set-file", value_name = "KEY=PATH")]
+    set_file: Vec<String>,
+    /// How --set and --set-file values are applied
+    #[arg(long, value_enum, default_value = "override")]
+    set_mode: SetMode,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every table in a file with its directives and dependencies
+    List {
+        file: String,
+        #[arg(short, long, value_enum, default_value = "text")]
+        output: ReportFormat,
+    },
+    /// Run a command with the resolved tables as its environment
+    Exec {
+        file: String,
+        /// Tables to resolve; values from later tables override earlier ones
+        #[arg(required = true, num_args = 1..)]
+        tables: Vec<String>,
+        /// Start the command with only the resolved variables
+        #[arg(long)]
+        clean_env: bool,
+        /// Prefix added to every variable name
+        #[arg(long)]
+        prefix: Option<String>,
+        #[command(flatten)]
+        set: SetArgs,
+        /// Command and arguments to run, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Show where each resolved value came from and what it replaced
+    Explain {
+        file: String,
+        /// Tables to resolve; values from later tables override earlier ones
+        #[arg(required = true, num_args = 1..)]
+        tables: Vec<String>,
+        /// Only explain this key
+        #[arg(short, long)]
+        key: Option<String>,
+        /// Output format the values are resolved for
+        #[arg(short, long, value_enum, default_value = "toml")]
+        output: OutputArg,
+        /// How the explanation is printed
+        #[arg(long, value_enum, default_value = "text")]
+        report: ReportFormat,
+        #[command(flatten)]
+        set: SetArgs,
+    },
+    /// Write every output listed in the file's `_.outputs` directive
+    Render {
+        file: String,
+        #[command(flatten)]
+        set: SetArgs,
+    },
 }
 
 fn main() {
     let args = Args::parse();
+    let _ = ERROR_FORMAT.set(args.error_format);
+    let _ = ALLOW_EXEC.set(args.allow_exec);
 
-    if let Err(e) = change_to_file_directory(&args.file) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
+    let result = match &args.command {
+        Some(Command::List { file, output }) => list(file, output),
+        Some(Command::Exec {
+            file,
+            tables,
+            clean_env,
+            prefix,
+            set,
+            command,
+        }) => {
+            let overrides = prepare(set);
+            let code = exec(
+                file,
+                tables,
+                &overrides,
+                set.set_mode.into(),
+                &ExecOptions {
+                    clean_env: *clean_env,
+                    prefix: prefix.as_deref().unwrap_or_default(),
+                    command,
+                },
+            )
+            .unwrap_or_else(|e| exit_with_supertoml_error(&e));
+            std::process::exit(code);
+        }
+        Some(Command::Explain {
+            file,
+            tables,
+            key,
+            output,
+            report,
+            set,
+        }) => {
+            let tables: Vec<&str> = tables.iter().map(String::as_str).collect();
+            let overrides = prepare(set);
+            explain(
+                file,
+                &tables,
+                key.as_deref(),
+                (*output).into(),
+                report,
+                &overrides,
+                set.set_mode.into(),
+            )
+        }
+        Some(Command::Render { file, set }) => {
+            let overrides = prepare(set);
+            render(file, &overrides, set.set_mode.into())
+        }
+        None => {
+            let file = args.file.as_deref().unwrap_or_default();
+            let tables: Vec<&str> = args.tables.iter().map(String::as_str).collect();
+            let overrides = prepare(&args.set);
 
-    match run(&args) {
-        Ok(output) => println!("{}", output),
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            run(
+                file,
+                &tables,
+                args.output.into(),
+                &overrides,
+                args.set.set_mode.into(),
+            )
         }
+    };
+
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(e) => exit_with_supertoml_error(&e),
     }
 }
 
-fn change_to_file_directory(file_path: &str) -> Result<(), String> {
-    use std::path::Path;
-
-    let path = Path::new(file_path);
-
-    let absolute_path = path
-        .canonicalize()
-        .map_err(|e| format!("Failed to resolve file path '{}': {}", file_path, e))?;
+fn report_message(message: &str) {
+    match ERROR_FORMAT.get().copied().unwrap_or_default() {
+        ErrorFormat::Human => eprintln!("Error: {}", message),
+        ErrorFormat::Json => eprintln!("{}", serde_json::json!({ "message": message })),
+    }
+}
 
-    if let Some(parent) = absolute_path.parent() {
-        std::env::set_current_dir(parent).map_err(|e| {
-            format!(
-                "Failed to change to directory '{}': {}",
-                parent.display(),
-                e
-            )
-        })?;
+/// Print warnings collected while resolving, in the error format
+///
+/// Tables resolved more than once repeat their warnings, which are shown once.
+fn report_warnings(resolver: &supertoml::Resolver) {
+    let mut seen = std::collections::HashSet::new();
+    for warning in resolver.warnings.iter().filter(|w| seen.insert(*w)) {
+        match ERROR_FORMAT.get().copied().unwrap_or_default() {
+            ErrorFormat::Human => eprintln!("Warning: {}", warning),
+            ErrorFormat::Json => eprintln!("{}", serde_json::json!({ "warning": warning })),
+        }
     }
+}
 
-    Ok(())
+fn exit_with_supertoml_error(e: &supertoml::SuperTomlError) -> ! {
+    match ERROR_FORMAT.get().copied().unwrap_or_default() {
+        ErrorFormat::Human => eprintln!("{}", supertoml::diagnostics::format_error(e)),
+        ErrorFormat::Json => eprintln!("{}", supertoml::diagnostics::error_to_json(e)),
+    }
+    std::process::exit(1);
 }
 
-fn run(args: &Args) -> Result<String, supertoml::SuperTomlError> {
-    use std::path::Path;
+/// Read command line values
+///
+/// `--set-file` paths are relative to where the command was run.
+fn prepare(set: &SetArgs) -> Vec<Override> {
+    parse_overrides(&set.set, &set.set_file).unwrap_or_else(|e| exit_with_supertoml_error(&e))
+}
 
-    let mut resolver = supertoml::Resolver::new(vec![
+fn create_resolver() -> supertoml::Resolver {
+    supertoml::Resolver::new(vec![
         &supertoml::plugins::BeforePlugin as &dyn supertoml::Plugin,
         &supertoml::plugins::ImportPlugin as &dyn supertoml::Plugin,
         &supertoml::plugins::TemplatingPlugin as &dyn supertoml::Plugin,
         &supertoml::plugins::AfterPlugin as &dyn supertoml::Plugin,
-    ]);
-
-    let filename = Path::new(&args.file)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or(&args.file);
-
-    let resolved_values =
-        resolver.resolve_table_with_meta(filename, &args.table, &args.output.to_string())?;
-
-    match args.output {
-        OutputFormat::Toml => supertoml::format_as_toml(&resolved_values),
-        OutputFormat::Json => supertoml::format_as_json(&resolved_values),
-        OutputFormat::Dotenv => supertoml::format_as_dotenv(&resolved_values),
-        OutputFormat::Exports => supertoml::format_as_exports(&resolved_values),
-        OutputFormat::Tfvars => supertoml::format_as_tfvars(&resolved_values),
+    ])
+}
+
+fn load_resolver(file: &str) -> Result<supertoml::Resolver, supertoml::SuperTomlError> {
+    let mut resolver = create_resolver();
+    resolver.allow_exec = ALLOW_EXEC.get().copied().unwrap_or_default();
+    resolver.load_file(file)?;
+    Ok(resolver)
+}
+
+fn resolve(
+    file: &str,
+    tables: &[&str],
+    output_format: &str,
+    overrides: &[Override],
+    set_mode: OverrideMode,
+) -> Result<HashMap<String, toml::Value>, supertoml::SuperTomlError> {
+    let mut resolver = load_resolver(file)?;
+    let values = resolve_with_overrides(
+        &mut resolver,
+        tables,
+        Some(output_format),
+        overrides,
+        set_mode,
+    )?;
+    report_warnings(&resolver);
+    Ok(values)
+}
+
+fn run(
+    file: &str,
+    tables: &[&str],
+    output: OutputFormat,
+    overrides: &[Override],
+    set_mode: OverrideMode,
+) -> Result<String, supertoml::SuperTomlError> {
+    let resolved_values = resolve(file, tables, &output.to_string(), overrides, set_mode)?;
+    supertoml::format_values(&resolved_values, output)
+}
+
+fn explain(
+    file: &str,
+    tables: &[&str],
+    key: Option<&str>,
+    output: OutputFormat,
+    report: &ReportFormat,
+    overrides: &[Override],
+    set_mode: OverrideMode,
+) -> Result<String, supertoml::SuperTomlError> {
+    let mut resolver = load_resolver(file)?;
+    let values = resolve_with_overrides(
+        &mut resolver,
+        tables,
+        Some(&output.to_string()),
+        overrides,
+        set_mode,
+    )?;
+    report_warnings(&resolver);
+
+    let mut keys: Vec<&String> = match key {
+        Some(key) => vec![values.get_key_value(key).map(|(k, _)| k).ok_or_else(|| {
+            supertoml::SuperTomlError::KeyNotFound {
+                key: key.to_string(),
+                table: tables.join(", "),
+            }
+        })?],
+        None => values.keys().collect(),
+    };
+    keys.sort();
+
+    match report {
+        ReportFormat::Text => {
+            let mut report = supertoml::provenance::format_provenance(&resolver.provenance, &keys);
+            for note in &resolver.notes {
+                report.push_str(&format!("\n\nnote: {}", note));
+            }
+            Ok(report)
+        }
+        ReportFormat::Json => serde_json::to_string_pretty(
+            &supertoml::provenance::provenance_to_json(&resolver.provenance, &keys),
+        )
+        .map_err(|e| supertoml::SuperTomlError::SerializationError(e.to_string())),
+    }
+}
+
+/// Render every `_.outputs` entry with one resolver and write them together
+fn render(
+    file: &str,
+    overrides: &[Override],
+    set_mode: OverrideMode,
+) -> Result<String, supertoml::SuperTomlError> {
+    let mut resolver = load_resolver(file)?;
+    let targets = match &resolver.toml_file {
+        Some(toml_file) => supertoml::render::output_targets(toml_file)?,
+        None => Vec::new(),
+    };
+
+    // Paths are relative to the file
+    let base_dir = resolver.base_dir.clone().unwrap_or_default();
+    let outputs =
+        supertoml::render::render_outputs(&mut resolver, &targets, &base_dir, overrides, set_mode)?;
+    supertoml::render::write_outputs(&outputs)?;
+
+    Ok(outputs
+        .iter()
+        .map(|output| format!("Wrote {}", output.path.display()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+struct ExecOptions<'a> {
+    clean_env: bool,
+    prefix: &'a str,
+    command: &'a [String],
+}
+
+/// Run a command with the resolved values as environment variables and
+/// return its exit code
+fn exec(
+    file: &str,
+    tables: &[String],
+    overrides: &[Override],
+    set_mode: OverrideMode,
+    options: &ExecOptions,
+) -> Result<i32, supertoml::SuperTomlError> {
+    let tables: Vec<&str> = tables.iter().map(String::as_str).collect();
+    let resolved_values = resolve(file, &tables, "exec", overrides, set_mode)?;
+    let variables = supertoml::format_as_env_vars(&resolved_values, options.prefix)?;
+
+    // clap requires at least one command argument
+    let Some((program, args)) = options.command.split_first() else {
+        return Ok(0);
+    };
+
+    let mut child = std::process::Command::new(program);
+    child.args(args);
+    if options.clean_env {
+        child.env_clear();
+    }
+    child.envs(variables);
+
+    match child.status() {
+        Ok(status) => Ok(exit_code(status)),
+        Err(e) => {
+            // Follow the shell convention for commands that cannot be started
+            report_message(&format!("Failed to run '{}': {}", program, e));
+            match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(127),
+                _ => Ok(126),
+            }
+        }
+    }
+}
+
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+fn parse_overrides(
+    set: &[String],
+    set_file: &[String],
+) -> Result<Vec<Override>, supertoml::SuperTomlError> {
+    let mut overrides = Vec::new();
+    for arg in set {
+        overrides.push(Override::parse(arg)?);
+    }
+    for arg in set_file {
+        overrides.push(Override::from_file(arg)?);
+    }
+    Ok(overrides)
+}
+
+fn list(file: &str, output: &ReportFormat) -> Result<String, supertoml::SuperTomlError> {
+    let resolver = create_resolver();
+    let toml_file = supertoml::loader::load_toml_file(file)?;
+    let tables = supertoml::listing::list_tables(&toml_file, &resolver.plugins);
+
+    match output {
+        ReportFormat::Text => Ok(supertoml::listing::format_table_list(&tables)),
+        ReportFormat::Json => serde_json::to_string_pretty(&tables)
+            .map_err(|e| supertoml::SuperTomlError::SerializationError(e.to_string())),
     }
 }
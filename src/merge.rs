@@ -0,0 +1,202 @@
+//! Merging a table's values into values already set by other tables
+
+use crate::SuperTomlError;
+use serde::{Deserialize, Serialize};
+
+/// How a table value replaces a table already set under the same key
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableMerge {
+    /// The new table replaces the old one
+    #[default]
+    Replace,
+    /// Keys of the new table are merged into the old one, recursively
+    Deep,
+}
+
+/// How an array value replaces an array already set under the same key
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayMerge {
+    /// The new array replaces the old one
+    #[default]
+    Replace,
+    /// Items of the new array are added after the old ones
+    Append,
+    /// As `Append`, leaving out items the array already has
+    Unique,
+}
+
+/// The merge strategy in effect for a table
+///
+/// The default replaces values, so that the last table to set a key wins.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStrategy {
+    pub tables: TableMerge,
+    pub arrays: ArrayMerge,
+}
+
+/// A `_.merge` directive, overriding parts of the strategy it is applied to
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MergeConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tables: Option<TableMerge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrays: Option<ArrayMerge>,
+}
+
+impl MergeConfig {
+    pub fn from_value(value: toml::Value) -> Result<Self, SuperTomlError> {
+        value
+            .try_into()
+            .map_err(|e: toml::de::Error| SuperTomlError::PluginDeserialization {
+                plugin_name: "merge".to_string(),
+                error: e.message().to_string(),
+            })
+    }
+}
+
+impl MergeStrategy {
+    /// This strategy with the parts set in `config` replaced
+    pub fn overlay(self, config: &MergeConfig) -> Self {
+        Self {
+            tables: config.tables.unwrap_or(self.tables),
+            arrays: config.arrays.unwrap_or(self.arrays),
+        }
+    }
+
+    /// Merge `value` into `base`, the value it replaces
+    ///
+    /// Values of different types are always replaced.
+    pub fn merge(&self, base: &toml::Value, value: toml::Value) -> toml::Value {
+        match (base, value) {
+            (toml::Value::Table(base), toml::Value::Table(table))
+                if self.tables == TableMerge::Deep =>
+            {
+                let mut merged = base.clone();
+                for (key, value) in table {
+                    let value = match merged.get(&key) {
+                        Some(previous) => self.merge(previous, value),
+                        None => value,
+                    };
+                    merged.insert(key, value);
+                }
+                toml::Value::Table(merged)
+            }
+            (toml::Value::Array(base), toml::Value::Array(items)) => match self.arrays {
+                ArrayMerge::Replace => toml::Value::Array(items),
+                ArrayMerge::Append => {
+                    toml::Value::Array(base.iter().cloned().chain(items).collect())
+                }
+                ArrayMerge::Unique => {
+                    let mut merged = base.clone();
+                    for item in items {
+                        if !merged.contains(&item) {
+                            merged.push(item);
+                        }
+                    }
+                    toml::Value::Array(merged)
+                }
+            },
+            (_, value) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml::Value;
+
+    fn strategy(tables: TableMerge, arrays: ArrayMerge) -> MergeStrategy {
+        MergeStrategy { tables, arrays }
+    }
+
+    fn strings(items: &[&str]) -> Value {
+        Value::Array(items.iter().map(|&item| Value::from(item)).collect())
+    }
+
+    #[test]
+    fn test_default_replaces() {
+        let base = Value::Table(toml::toml! { host = "x" port = 1 });
+        let value = Value::Table(toml::toml! { host = "y" });
+        assert_eq!(MergeStrategy::default().merge(&base, value.clone()), value);
+    }
+
+    #[test]
+    fn test_deep_merges_nested_tables() {
+        let base = Value::Table(toml::toml! {
+            host = "x"
+            port = 1
+            [pool]
+            min = 1
+            max = 5
+        });
+        let value = Value::Table(toml::toml! {
+            host = "y"
+            [pool]
+            max = 10
+        });
+        let expected = Value::Table(toml::toml! {
+            host = "y"
+            port = 1
+            [pool]
+            min = 1
+            max = 10
+        });
+        let merge = strategy(TableMerge::Deep, ArrayMerge::Replace);
+        assert_eq!(merge.merge(&base, value), expected);
+    }
+
+    #[test]
+    fn test_array_strategies() {
+        let base = strings(&["a", "b"]);
+        let value = strings(&["b", "c"]);
+
+        let merged = strategy(TableMerge::Replace, ArrayMerge::Replace).merge(&base, value.clone());
+        assert_eq!(merged, value);
+
+        let merged = strategy(TableMerge::Replace, ArrayMerge::Append).merge(&base, value.clone());
+        assert_eq!(merged, strings(&["a", "b", "b", "c"]));
+
+        let merged = strategy(TableMerge::Replace, ArrayMerge::Unique).merge(&base, value);
+        assert_eq!(merged, strings(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_arrays_inside_replaced_tables_are_not_merged() {
+        let base = Value::Table(toml::toml! { hosts = ["a"] });
+        let value = Value::Table(toml::toml! { hosts = ["b"] });
+        let merge = strategy(TableMerge::Replace, ArrayMerge::Append);
+        assert_eq!(merge.merge(&base, value.clone()), value);
+
+        let merge = strategy(TableMerge::Deep, ArrayMerge::Append);
+        assert_eq!(
+            merge.merge(&base, value),
+            Value::Table(toml::toml! { hosts = ["a", "b"] })
+        );
+    }
+
+    #[test]
+    fn test_different_types_are_replaced() {
+        let base = Value::Table(toml::toml! { host = "x" });
+        let merge = strategy(TableMerge::Deep, ArrayMerge::Append);
+        assert_eq!(merge.merge(&base, Value::from(1)), Value::from(1));
+    }
+
+    #[test]
+    fn test_overlay_keeps_unset_parts() {
+        let config =
+            MergeConfig::from_value(Value::Table(toml::toml! { arrays = "unique" })).unwrap();
+        let merged = strategy(TableMerge::Deep, ArrayMerge::Replace).overlay(&config);
+        assert_eq!(merged, strategy(TableMerge::Deep, ArrayMerge::Unique));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let error =
+            MergeConfig::from_value(Value::Table(toml::toml! { tables = "shallow" })).unwrap_err();
+        assert!(error.to_string().contains("unknown variant"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Values supplied on the command line with `--set` and `--set-file`
+
+use crate::error::SuperTomlError;
+use crate::loader::parse_table_path;
+use crate::provenance::{self, Provenance, ValueSource};
+use crate::Resolver;
+use std::collections::HashMap;
+use std::fs;
+
+/// When command line values are applied during resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverrideMode {
+    /// Replace resolved values after all tables are processed
+    Override,
+    /// Provide starting values that templates can use and tables can replace
+    Seed,
+}
+
+/// A single `key=value` override, where `key` may be a dotted path
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub path: Vec<String>,
+    pub value: toml::Value,
+    /// The option the value was given with, `--set` or `--set-file`
+    pub flag: &'static str,
+}
+
+impl Override {
+    /// Parse a `--set key=value` argument, reading the value as a TOML literal
+    pub fn parse(arg: &str) -> Result<Self, SuperTomlError> {
+        let (path, value) = split_assignment(arg)?;
+        Ok(Self {
+            path,
+            value: parse_value_literal(value),
+            flag: "--set",
+        })
+    }
+
+    /// Parse a `--set-file key=path` argument, using the file contents as a string
+    pub fn from_file(arg: &str) -> Result<Self, SuperTomlError> {
+        let (path, file) = split_assignment(arg)?;
+        let content = fs::read_to_string(file).map_err(|e| SuperTomlError::InvalidOverride {
+            arg: arg.to_string(),
+            reason: format!("failed to read '{}': {}", file, e),
+        })?;
+        Ok(Self {
+            path,
+            value: toml::Value::String(content),
+            flag: "--set-file",
+        })
+    }
+}
+
+/// Read a value as a TOML literal, falling back to a plain string
+///
+/// `8080` becomes an integer, `true` a boolean and `[1, 2]` an array, while
+/// anything that is not valid TOML (such as `hello` or `1.0.0`) is kept as
+/// the string it was written as.
+pub fn parse_value_literal(value: &str) -> toml::Value {
+    format!("value = {}", value)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// Apply overrides in order, creating intermediate tables for dotted keys
+pub fn apply_overrides(values: &mut HashMap<String, toml::Value>, overrides: &[Override]) {
+    for item in overrides {
+        let Some((first, rest)) = item.path.split_first() else {
+            continue;
+        };
+
+        if rest.is_empty() {
+            values.insert(first.clone(), item.value.clone());
+            continue;
+        }
+
+        let entry = values
+            .entry(first.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        insert_at_path(entry, rest, item.value.clone());
+    }
+}
+
+/// Resolve tables from the resolver's loaded file, applying overrides
+/// according to `mode`
+pub fn resolve_with_overrides(
+    resolver: &mut Resolver,
+    table_names: &[&str],
+    output_format: Option<&str>,
+    overrides: &[Override],
+    mode: OverrideMode,
+) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+    if mode == OverrideMode::Seed {
+        apply_overrides(&mut resolver.values, overrides);
+        record_overrides(&mut resolver.provenance, &resolver.values, overrides);
+    }
+
+    let mut values = resolver.resolve_loaded_tables(table_names, output_format)?;
+
+    if mode == OverrideMode::Override {
+        apply_overrides(&mut values, overrides);
+        record_overrides(&mut resolver.provenance, &values, overrides);
+    }
+
+    Ok(values)
+}
+
+/// Record overrides as the source of the top-level values they changed
+fn record_overrides(
+    provenance: &mut HashMap<String, Provenance>,
+    values: &HashMap<String, toml::Value>,
+    overrides: &[Override],
+) {
+    for item in overrides {
+        let Some(value) = item.path.first().and_then(|key| values.get(key)) else {
+            continue;
+        };
+        provenance::record(
+            provenance,
+            &item.path[0],
+            ValueSource {
+                file: None,
+                table: None,
+                plugin: Some(item.flag.to_string()),
+                raw: item.value.clone(),
+                value: value.clone(),
+            },
+        );
+    }
+}
+
+fn insert_at_path(target: &mut toml::Value, path: &[String], value: toml::Value) {
+    if !target.is_table() {
+        *target = toml::Value::Table(toml::Table::new());
+    }
+    let Some(table) = target.as_table_mut() else {
+        return;
+    };
+
+    match path {
+        [] => {}
+        [last] => {
+            table.insert(last.clone(), value);
+        }
+        [first, rest @ ..] => {
+            let entry = table
+                .entry(first.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            insert_at_path(entry, rest, value);
+        }
+    }
+}
+
+fn split_assignment(arg: &str) -> Result<(Vec<String>, &str), SuperTomlError> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| SuperTomlError::InvalidOverride {
+            arg: arg.to_string(),
+            reason: "expected key=value".to_string(),
+        })?;
+
+    let path = parse_table_path(key.trim()).map_err(|e| SuperTomlError::InvalidOverride {
+        arg: arg.to_string(),
+        reason: e.to_string(),
+    })?;
+
+    Ok((path, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value_literal() {
+        assert_eq!(parse_value_literal("8080"), toml::Value::Integer(8080));
+        assert_eq!(parse_value_literal("true"), toml::Value::Boolean(true));
+        assert_eq!(
+            parse_value_literal("[1, 2]"),
+            toml::Value::Array(vec![toml::Value::Integer(1), toml::Value::Integer(2)])
+        );
+        assert_eq!(
+            parse_value_literal("\"8080\""),
+            toml::Value::String("8080".to_string())
+        );
+        assert_eq!(
+            parse_value_literal("1.0.0"),
+            toml::Value::String("1.0.0".to_string())
+        );
+        assert_eq!(parse_value_literal(""), toml::Value::String(String::new()));
+    }
+
+    #[test]
+    fn test_override_parse() {
+        let item = Override::parse("db.port=5432").unwrap();
+        assert_eq!(item.path, vec!["db", "port"]);
+        assert_eq!(item.value, toml::Value::Integer(5432));
+
+        let item = Override::parse("url=http://host/?a=b").unwrap();
+        assert_eq!(item.path, vec!["url"]);
+        assert_eq!(item.value.as_str(), Some("http://host/?a=b"));
+
+        assert!(Override::parse("missing").is_err());
+        assert!(Override::parse("=value").is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut values: HashMap<String, toml::Value> = HashMap::new();
+        values.insert("port".to_string(), toml::Value::Integer(80));
+        values.insert("db".to_string(), toml::Value::String("flat".to_string()));
+
+        apply_overrides(
+            &mut values,
+            &[
+                Override::parse("port=8080").unwrap(),
+                Override::parse("db.host=localhost").unwrap(),
+                Override::parse("db.port=5432").unwrap(),
+            ],
+        );
+
+        assert_eq!(values["port"], toml::Value::Integer(8080));
+        let db = values["db"].as_table().unwrap();
+        assert_eq!(db["host"].as_str(), Some("localhost"));
+        assert_eq!(db["port"].as_integer(), Some(5432));
+    }
+}
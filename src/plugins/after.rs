@@ -24,6 +24,16 @@ impl Plugin for AfterPlugin {
 
         Ok(())
     }
+
+    fn dependencies(&self, config: &toml::Value) -> Vec<crate::listing::Dependency> {
+        config
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(|table_name| crate::listing::Dependency::table(self.name(), table_name))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -68,8 +78,8 @@ mod tests {
 
         assert_eq!(resolver.values.len(), 0);
 
-        assert!(resolver.values.get("key1").is_none());
-        assert!(resolver.values.get("key2").is_none());
+        assert!(!resolver.values.contains_key("key1"));
+        assert!(!resolver.values.contains_key("key2"));
     }
 
     #[test]
@@ -1,5 +1,7 @@
 use crate::{
     extract_config,
+    loader::{normalize_path, FileFormat},
+    provenance::ValueSource,
     utils::{
         add_values_to_resolver, create_template_environment_with_meta, template_error,
         toml_value_to_jinja,
@@ -8,15 +10,91 @@ use crate::{
 };
 use minijinja::Value as JinjaValue;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
-#[derive(Deserialize, serde::Serialize)]
+#[derive(Debug, Default, Deserialize, serde::Serialize)]
 pub struct ImportConfig {
-    pub file: String,
-    pub table: String,
+    /// File (or glob pattern) to import from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Import the process environment variables starting with this prefix
+    /// instead of a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Run this command and import its output instead of a file; needs
+    /// [`crate::Resolver::allow_exec`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Read `file` as it is at this git revision instead of from the working
+    /// tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// Remove the `env` prefix from imported variable names
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strip_prefix: bool,
+    /// Read environment variable values as TOML literals, so `8080` is an
+    /// integer, falling back to strings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub infer_types: bool,
+    /// Table path to import; the whole file when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
     pub key_format: Option<String>,
+    /// Format of `file`; chosen from the file extension when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FileFormat>,
+    /// Resolve the imported table's own directives and templates before
+    /// importing its values
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resolve: bool,
+    /// Skip the import when `file` does not exist or the pattern matches
+    /// nothing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    /// Key patterns to import; every key when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    /// Key patterns to leave out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// New names for imported keys, applied before `key_format`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rename: HashMap<String, String>,
+    /// What to do when two imported keys end up with the same name, or an
+    /// imported key replaces one defined in the importing table
+    #[serde(default)]
+    pub on_collision: OnCollision,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnCollision {
+    /// Fail the import
+    #[default]
+    Error,
+    /// Record a warning and let the imported value win
+    Warn,
+}
+
+impl ImportConfig {
+    /// Describe where values are imported from, for messages
+    fn source_name(&self) -> String {
+        match (&self.file, &self.env, &self.command) {
+            (Some(file), _, _) => match &self.git_ref {
+                Some(git_ref) => format!("{}@{}", file, git_ref),
+                None => file.clone(),
+            },
+            (None, Some(prefix), _) => format!("${}*", prefix),
+            (None, None, Some(command)) => command.join(" "),
+            (None, None, None) => String::new(),
+        }
+    }
+}
+
+/// An imported key with its value and where it came from
+type ImportedValue = (String, toml::Value, ValueSource);
+
 pub struct ImportPlugin;
 
 impl Plugin for ImportPlugin {
@@ -40,8 +118,15 @@ impl Plugin for ImportPlugin {
         let import_configs: Vec<ImportConfig> =
             extract_config!(config, Vec<ImportConfig>, self.name())?;
 
+        // Keys written in the importing table itself
+        let local_keys: HashSet<String> = table_values
+            .keys()
+            .filter(|key| !resolver.is_inherited(key))
+            .cloned()
+            .collect();
+
         for import_config in import_configs {
-            self.process_single_import(&import_config, table_values, resolver)?;
+            self.process_single_import(&import_config, &local_keys, table_values, resolver)?;
         }
 
         // Add all table_values to resolver.values (following the pattern from other plugins)
@@ -49,6 +134,38 @@ impl Plugin for ImportPlugin {
 
         Ok(())
     }
+
+    fn dependencies(&self, config: &toml::Value) -> Vec<crate::listing::Dependency> {
+        // Entries are read one at a time, so that a malformed entry, which
+        // resolving reports, still lists what can be read from it and does
+        // not hide the others
+        config
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let import_config: ImportConfig = entry.clone().try_into().unwrap_or_else(|_| {
+                    let field = |name: &str| entry.get(name)?.as_str().map(str::to_string);
+                    ImportConfig {
+                        file: field("file"),
+                        env: field("env"),
+                        git_ref: field("git_ref"),
+                        table: field("table"),
+                        ..Default::default()
+                    }
+                });
+                let source_name = import_config.source_name();
+                if source_name.is_empty() && import_config.table.is_none() {
+                    return None;
+                }
+                Some(crate::listing::Dependency {
+                    directive: self.name().to_string(),
+                    table: import_config.table,
+                    file: Some(source_name),
+                })
+            })
+            .collect()
+    }
 }
 
 impl ImportPlugin {
@@ -56,62 +173,473 @@ impl ImportPlugin {
     fn process_single_import(
         &self,
         import_config: &ImportConfig,
+        local_keys: &HashSet<String>,
         table_values: &mut HashMap<String, toml::Value>,
-        resolver: &crate::Resolver,
+        resolver: &mut crate::Resolver,
     ) -> Result<(), SuperTomlError> {
-        // Load the external TOML file
-        let external_toml = crate::loader::load_toml_file(&import_config.file)?;
+        let imported = match (
+            &import_config.file,
+            &import_config.env,
+            &import_config.command,
+        ) {
+            (Some(file), None, None) => match self.import_files(file, import_config, resolver)? {
+                Some(imported) => imported,
+                None => return Ok(()),
+            },
+            (None, Some(prefix), None) => self.import_env(prefix, import_config),
+            (None, None, Some(command)) => self.import_command(command, import_config, resolver)?,
+            _ => {
+                return Err(SuperTomlError::PluginError {
+                    plugin_name: self.name().to_string(),
+                    error: "each import needs exactly one of 'file', 'env' or 'command'"
+                        .to_string(),
+                })
+            }
+        };
+        let imported = self.select_keys(imported, import_config)?;
+        let source_name = import_config.source_name();
 
-        // Extract the specified table using idiomatic Rust
-        let table_data = self.extract_table_from_toml(&external_toml, import_config)?;
+        // Source key behind each key this import has written
+        let mut written: HashMap<String, String> = HashMap::new();
 
         // Process each key/value pair
-        for (key, value) in table_data {
+        for (key, value, source) in imported {
+            let renamed = import_config.rename.get(&key).unwrap_or(&key);
             let final_key = if let Some(ref key_format) = import_config.key_format {
                 // Transform the key using minijinja
                 self.transform_key_with_template(
-                    key,
+                    renamed,
                     key_format,
                     &resolver.values,
                     &resolver.meta_values,
                 )?
             } else {
-                key.clone()
+                renamed.clone()
             };
 
+            // Files matched by a glob may repeat a key on purpose, later
+            // files winning
+            let collision = match written.get(&final_key) {
+                Some(previous) if *previous != key => Some(format!(
+                    "keys '{}' and '{}' from '{}' are both imported as '{}'",
+                    previous, key, source_name, final_key
+                )),
+                Some(_) => None,
+                None if local_keys.contains(&final_key) => Some(format!(
+                    "key '{}' from '{}' is imported as '{}', which is already defined in the table",
+                    key, source_name, final_key
+                )),
+                None => None,
+            };
+            if let Some(collision) = collision {
+                match import_config.on_collision {
+                    OnCollision::Error => {
+                        return Err(SuperTomlError::PluginError {
+                            plugin_name: self.name().to_string(),
+                            error: collision,
+                        })
+                    }
+                    OnCollision::Warn => resolver.warnings.push(collision),
+                }
+            }
+            written.insert(final_key.clone(), key);
+
+            resolver.record_origin(&final_key, source);
+
             // Add the key/value pair to table_values
-            table_values.insert(final_key, value.clone());
+            table_values.insert(final_key, value);
         }
 
         Ok(())
     }
 
-    /// Extract a table from TOML with clear error messages
+    /// Read the keys and values of the files matching `file`, or `None`
+    /// when an optional import has no files
+    fn import_files(
+        &self,
+        file: &str,
+        import_config: &ImportConfig,
+        resolver: &mut crate::Resolver,
+    ) -> Result<Option<Vec<ImportedValue>>, SuperTomlError> {
+        let pattern = self.import_path(resolver, file);
+
+        let paths = if is_glob_pattern(file) {
+            if import_config.git_ref.is_some() {
+                return Err(SuperTomlError::PluginError {
+                    plugin_name: self.name().to_string(),
+                    error: format!("'git_ref' cannot be used with the pattern '{}'", file),
+                });
+            }
+            self.expand_glob(&pattern)?
+        } else if import_config.git_ref.is_some() {
+            // The file may only exist at the revision
+            vec![pattern.clone()]
+        } else if import_config.optional && !Path::new(&pattern).exists() {
+            Vec::new()
+        } else {
+            vec![pattern.clone()]
+        };
+
+        if paths.is_empty() {
+            if !import_config.optional {
+                return Err(SuperTomlError::PluginError {
+                    plugin_name: self.name().to_string(),
+                    error: format!("no files match '{}'", file),
+                });
+            }
+            resolver
+                .notes
+                .push(format!("skipped optional import '{}': not found", pattern));
+            return Ok(None);
+        }
+
+        // Matches are imported in sorted order, later files winning
+        let mut imported = Vec::new();
+        for path in paths {
+            imported.extend(self.import_file(&path, import_config, resolver)?);
+        }
+        Ok(Some(imported))
+    }
+
+    /// Read the process environment variables starting with `prefix`, in
+    /// sorted order
+    fn import_env(&self, prefix: &str, import_config: &ImportConfig) -> Vec<ImportedValue> {
+        let mut variables: Vec<(String, String)> = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .filter(|(name, _)| name.starts_with(prefix) && name.len() > prefix.len())
+            .collect();
+        variables.sort();
+
+        variables
+            .into_iter()
+            .map(|(name, raw)| {
+                let value = if import_config.infer_types {
+                    crate::overrides::parse_value_literal(&raw)
+                } else {
+                    toml::Value::String(raw.clone())
+                };
+                let source = ValueSource {
+                    file: None,
+                    table: Some(format!("${}", name)),
+                    plugin: Some(self.name().to_string()),
+                    raw: toml::Value::String(raw),
+                    value: value.clone(),
+                };
+                let key = match import_config.strip_prefix {
+                    true => name[prefix.len()..].to_string(),
+                    false => name,
+                };
+                (key, value, source)
+            })
+            .collect()
+    }
+
+    /// Run a command in the importing file's directory and read its output
+    /// in the import's format, TOML by default
+    fn import_command(
+        &self,
+        command: &[String],
+        import_config: &ImportConfig,
+        resolver: &crate::Resolver,
+    ) -> Result<Vec<ImportedValue>, SuperTomlError> {
+        let command_line = command.join(" ");
+        if !resolver.allow_exec {
+            return Err(SuperTomlError::PluginError {
+                plugin_name: self.name().to_string(),
+                error: format!(
+                    "importing the output of '{}' runs a command, which is disabled; \
+                     pass --allow-exec to enable it",
+                    command_line
+                ),
+            });
+        }
+        let Some((program, args)) = command.split_first() else {
+            return Err(SuperTomlError::PluginError {
+                plugin_name: self.name().to_string(),
+                error: "'command' is empty".to_string(),
+            });
+        };
+
+        let mut child = std::process::Command::new(program);
+        child.args(args).stdin(std::process::Stdio::null());
+        if let Some(base_dir) = resolver
+            .base_dir
+            .as_ref()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            child.current_dir(base_dir);
+        }
+
+        let output = child.output().map_err(|e| SuperTomlError::CommandFailed {
+            command: command_line.clone(),
+            status: None,
+            stderr: e.to_string(),
+        })?;
+        if !output.status.success() {
+            return Err(SuperTomlError::CommandFailed {
+                command: command_line,
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr)
+                    .trim_end()
+                    .to_string(),
+            });
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|_| SuperTomlError::PluginError {
+            plugin_name: self.name().to_string(),
+            error: format!("output of '{}' is not valid UTF-8", command_line),
+        })?;
+        let format = import_config.format.unwrap_or(FileFormat::Toml);
+        let parsed = crate::loader::parse_as_toml(&stdout, format).map_err(|e| {
+            e.map_root(|root| SuperTomlError::PluginError {
+                plugin_name: self.name().to_string(),
+                error: format!("output of '{}': {}", command_line, root.message()),
+            })
+        })?;
+        let table_data = self.extract_table_from_toml(&parsed, &command_line, import_config)?;
+
+        Ok(table_data
+            .iter()
+            .map(|(key, value)| {
+                let source = ValueSource {
+                    file: Some(command_line.clone()),
+                    table: import_config.table.clone(),
+                    plugin: Some(self.name().to_string()),
+                    raw: value.clone(),
+                    value: value.clone(),
+                };
+                (key.clone(), value.clone(), source)
+            })
+            .collect())
+    }
+
+    /// Read the keys and values of one file
+    fn import_file(
+        &self,
+        path: &str,
+        import_config: &ImportConfig,
+        resolver: &mut crate::Resolver,
+    ) -> Result<Vec<ImportedValue>, SuperTomlError> {
+        if import_config.resolve {
+            return self.resolve_imported_table(resolver, path, import_config);
+        }
+
+        // Load the external file, converting other formats to TOML values
+        let (file, external_toml) = match &import_config.git_ref {
+            Some(git_ref) => (
+                format!("{}@{}", path, git_ref),
+                crate::loader::load_git_file_as_toml(path, git_ref, import_config.format)?.1,
+            ),
+            None => (
+                path.to_string(),
+                crate::loader::load_file_as_toml(path, import_config.format)?,
+            ),
+        };
+
+        // Extract the specified table, or the root table when none is given
+        let table_data = self.extract_table_from_toml(&external_toml, &file, import_config)?;
+
+        Ok(table_data
+            .iter()
+            .map(|(key, value)| {
+                let source = ValueSource {
+                    file: Some(file.clone()),
+                    table: import_config.table.clone(),
+                    plugin: Some(self.name().to_string()),
+                    raw: value.clone(),
+                    value: value.clone(),
+                };
+                (key.clone(), value.clone(), source)
+            })
+            .collect())
+    }
+
+    /// Apply `include` and `exclude` to imported keys
+    ///
+    /// Patterns match the keys as they are in the imported file. Every
+    /// `include` pattern must match at least one key.
+    fn select_keys(
+        &self,
+        imported: Vec<ImportedValue>,
+        import_config: &ImportConfig,
+    ) -> Result<Vec<ImportedValue>, SuperTomlError> {
+        let patterns = |globs: &[String]| {
+            globs
+                .iter()
+                .map(|glob| {
+                    glob::Pattern::new(glob).map_err(|e| SuperTomlError::PluginError {
+                        plugin_name: self.name().to_string(),
+                        error: format!("invalid key pattern '{}': {}", glob, e),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let include = import_config.include.as_deref().map(patterns).transpose()?;
+        let exclude = patterns(&import_config.exclude)?;
+
+        if let Some(include) = &include {
+            if let Some(unmatched) = include
+                .iter()
+                .find(|pattern| !imported.iter().any(|(key, _, _)| pattern.matches(key)))
+            {
+                return Err(SuperTomlError::PluginError {
+                    plugin_name: self.name().to_string(),
+                    error: format!(
+                        "include pattern '{}' matches no keys in '{}'",
+                        unmatched,
+                        import_config.source_name()
+                    ),
+                });
+            }
+        }
+
+        Ok(imported
+            .into_iter()
+            .filter(|(key, _, _)| {
+                include
+                    .as_ref()
+                    .is_none_or(|include| include.iter().any(|pattern| pattern.matches(key)))
+                    && !exclude.iter().any(|pattern| pattern.matches(key))
+            })
+            .collect())
+    }
+
+    /// Resolve an import path against the resolver's base directory
+    fn import_path(&self, resolver: &crate::Resolver, file: &str) -> String {
+        match &resolver.base_dir {
+            Some(base) if Path::new(file).is_relative() => {
+                normalize_path(&base.join(file)).display().to_string()
+            }
+            _ => file.to_string(),
+        }
+    }
+
+    /// List the files matching a glob pattern, sorted
+    fn expand_glob(&self, pattern: &str) -> Result<Vec<String>, SuperTomlError> {
+        let plugin_error = |error: String| SuperTomlError::PluginError {
+            plugin_name: self.name().to_string(),
+            error,
+        };
+
+        let mut paths = glob::glob(pattern)
+            .map_err(|e| plugin_error(format!("invalid file pattern '{}': {}", pattern, e)))?
+            .map(|entry| {
+                entry
+                    .map(|path| path.display().to_string())
+                    .map_err(|e| plugin_error(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Resolve the imported table with its own resolver, running the
+    /// imported file's directives, and return the values it produces
+    fn resolve_imported_table(
+        &self,
+        resolver: &mut crate::Resolver,
+        path: &str,
+        import_config: &ImportConfig,
+    ) -> Result<Vec<ImportedValue>, SuperTomlError> {
+        let table = import_config
+            .table
+            .as_deref()
+            .ok_or_else(|| SuperTomlError::PluginError {
+                plugin_name: self.name().to_string(),
+                error: format!("importing '{}' with resolve = true requires a table", path),
+            })?;
+
+        let mut child = crate::Resolver::new(resolver.plugins.clone());
+        child.allow_exec = resolver.allow_exec;
+        child.merge = resolver.merge;
+        let format = import_config
+            .format
+            .unwrap_or_else(|| FileFormat::from_path(path));
+        match (&import_config.git_ref, format) {
+            // Imports inside a file read from a revision still come from the
+            // working tree
+            (Some(git_ref), format) => {
+                let (source, toml) =
+                    crate::loader::load_git_file_as_toml(path, git_ref, Some(format))?;
+                child.toml_file = Some(toml);
+                child.source = Some(source);
+                child.file_path = Some(format!("{}@{}", path, git_ref));
+                child.base_dir = Path::new(path).parent().map(Path::to_path_buf);
+            }
+            (None, FileFormat::Toml) => child.load_file(path)?,
+            (None, format) => {
+                child.toml_file = Some(crate::loader::load_file_as_toml(path, Some(format))?);
+                child.file_path = Some(path.to_string());
+                child.base_dir = Path::new(path).parent().map(Path::to_path_buf);
+            }
+        }
+
+        // The imported table sees the same processing context, and shares the
+        // call stack so that imports leading back to a table are cycles
+        child.meta_values = resolver.meta_values.clone();
+        child.call_stack = resolver.call_stack.clone();
+        child.current_plugin = Some(self.name().to_string());
+
+        crate::resolve_table_recursive(&mut child, table)?;
+        resolver.notes.append(&mut child.notes);
+        resolver.warnings.append(&mut child.warnings);
+
+        let file = child.file_path.clone();
+        let mut provenance = std::mem::take(&mut child.provenance);
+        Ok(child
+            .values
+            .into_iter()
+            .map(|(key, value)| {
+                let source = match provenance.remove(&key) {
+                    Some(entry) => ValueSource {
+                        plugin: Some(self.name().to_string()),
+                        value: value.clone(),
+                        ..entry.source
+                    },
+                    None => ValueSource {
+                        file: file.clone(),
+                        table: Some(table.to_string()),
+                        plugin: Some(self.name().to_string()),
+                        raw: value.clone(),
+                        value: value.clone(),
+                    },
+                };
+                (key, value, source)
+            })
+            .collect())
+    }
+
+    /// Extract a table, or the root table when none is configured, from TOML
+    /// with clear error messages
     fn extract_table_from_toml<'a>(
         &self,
         toml: &'a toml::Value,
+        file: &str,
         config: &ImportConfig,
     ) -> Result<&'a toml::map::Map<String, toml::Value>, SuperTomlError> {
-        toml.as_table()
-            .ok_or_else(|| {
-                SuperTomlError::InvalidTableType(format!(
-                    "Root element in file '{}' is not a table",
-                    config.file
-                ))
-            })?
-            .get(&config.table)
-            .ok_or_else(|| {
-                SuperTomlError::TableNotFound(format!(
+        let root = toml.as_table().ok_or_else(|| {
+            SuperTomlError::InvalidTableType(format!(
+                "Root element in file '{}' is not a table",
+                file
+            ))
+        })?;
+
+        let Some(table) = &config.table else {
+            return Ok(root);
+        };
+
+        crate::loader::find_table(root, table)
+            .map(|(table, _)| table)
+            .map_err(|e| match e {
+                SuperTomlError::TableNotFound(_) => SuperTomlError::TableNotFound(format!(
                     "Table '{}' not found in file '{}'",
-                    config.table, config.file
-                ))
-            })?
-            .as_table()
-            .ok_or_else(|| {
-                SuperTomlError::TableNotFound(format!(
+                    table, file
+                )),
+                SuperTomlError::InvalidTableType(_) => SuperTomlError::TableNotFound(format!(
                     "Table '{}' in file '{}' is not a table",
-                    config.table, config.file
-                ))
+                    table, file
+                )),
+                other => other,
             })
     }
 
@@ -151,6 +679,11 @@ impl ImportPlugin {
     }
 }
 
+/// Whether an import path contains glob wildcards
+fn is_glob_pattern(file: &str) -> bool {
+    file.contains(['*', '?', '['])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,9 +731,10 @@ size = 1000
 
         let file_path = temp_file.path().to_str().unwrap().to_string();
         let config_vec = vec![ImportConfig {
-            file: file_path,
-            table: "database".to_string(),
+            file: Some(file_path),
+            table: Some("database".to_string()),
             key_format: None,
+            ..Default::default()
         }];
         let config = Value::try_from(config_vec).unwrap();
 
@@ -260,9 +794,10 @@ port = 443
 
         let file_path = temp_file.path().to_str().unwrap().to_string();
         let config_vec = vec![ImportConfig {
-            file: file_path,
-            table: "config".to_string(),
+            file: Some(file_path),
+            table: Some("config".to_string()),
             key_format: Some("api_{{key}}".to_string()),
+            ..Default::default()
         }];
         let config = Value::try_from(config_vec).unwrap();
 
@@ -280,8 +815,8 @@ port = 443
         );
 
         // Original keys should not exist
-        assert!(table_values.get("host").is_none());
-        assert!(table_values.get("port").is_none());
+        assert!(!table_values.contains_key("host"));
+        assert!(!table_values.contains_key("port"));
     }
 
     #[test]
@@ -311,14 +846,16 @@ port = 6379
         let file_path2 = temp_file2.path().to_str().unwrap().to_string();
         let config_vec = vec![
             ImportConfig {
-                file: file_path1,
-                table: "database".to_string(),
+                file: Some(file_path1),
+                table: Some("database".to_string()),
                 key_format: Some("db_{{key}}".to_string()),
+                ..Default::default()
             },
             ImportConfig {
-                file: file_path2,
-                table: "cache".to_string(),
+                file: Some(file_path2),
+                table: Some("cache".to_string()),
                 key_format: Some("cache_{{key}}".to_string()),
+                ..Default::default()
             },
         ];
         let config = Value::try_from(config_vec).unwrap();
@@ -349,6 +886,39 @@ port = 6379
         );
     }
 
+    #[test]
+    fn test_import_plugin_dotted_table_path() {
+        let (plugin, mut resolver, mut table_values) = create_test_setup();
+
+        let temp_file = create_temp_toml_file(
+            r#"
+[workspace.package]
+version = "1.2.3"
+edition = "2021"
+"#,
+        );
+
+        let config_vec = vec![ImportConfig {
+            file: Some(temp_file.path().to_str().unwrap().to_string()),
+            table: Some("workspace.package".to_string()),
+            key_format: None,
+            ..Default::default()
+        }];
+        let config = Value::try_from(config_vec).unwrap();
+
+        let result = plugin.process(&mut resolver, &mut table_values, config);
+        assert!(result.is_ok());
+
+        assert_eq!(
+            table_values.get("version").unwrap().as_str().unwrap(),
+            "1.2.3"
+        );
+        assert_eq!(
+            table_values.get("edition").unwrap().as_str().unwrap(),
+            "2021"
+        );
+    }
+
     #[test]
     fn test_import_plugin_file_not_found() {
         let plugin = ImportPlugin;
@@ -356,9 +926,10 @@ port = 6379
         let mut table_values = HashMap::new();
 
         let config_vec = vec![ImportConfig {
-            file: "nonexistent.toml".to_string(),
-            table: "test".to_string(),
+            file: Some("nonexistent.toml".to_string()),
+            table: Some("test".to_string()),
             key_format: None,
+            ..Default::default()
         }];
         let config = Value::try_from(config_vec).unwrap();
 
@@ -382,9 +953,10 @@ host = "localhost"
 
         let file_path = temp_file.path().to_str().unwrap().to_string();
         let config_vec = vec![ImportConfig {
-            file: file_path,
-            table: "nonexistent_table".to_string(),
+            file: Some(file_path),
+            table: Some("nonexistent_table".to_string()),
             key_format: None,
+            ..Default::default()
         }];
         let config = Value::try_from(config_vec).unwrap();
 
@@ -395,4 +967,234 @@ host = "localhost"
             .to_string()
             .contains("Table 'nonexistent_table' not found"));
     }
+
+    #[test]
+    fn test_import_plugin_whole_file() {
+        let (plugin, mut resolver, mut table_values) = create_test_setup();
+
+        let temp_file = create_temp_toml_file(
+            r#"
+name = "web"
+port = 8080
+
+[database]
+host = "localhost"
+"#,
+        );
+
+        let config_vec = vec![ImportConfig {
+            file: Some(temp_file.path().to_str().unwrap().to_string()),
+            key_format: Some("app_{{key}}".to_string()),
+            ..Default::default()
+        }];
+        let config = Value::try_from(config_vec).unwrap();
+
+        plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap();
+
+        assert_eq!(table_values.get("app_name").unwrap().as_str(), Some("web"));
+        assert_eq!(
+            table_values.get("app_port").unwrap().as_integer(),
+            Some(8080)
+        );
+        assert_eq!(
+            table_values.get("app_database").unwrap()["host"].as_str(),
+            Some("localhost")
+        );
+    }
+
+    #[test]
+    fn test_import_plugin_resolve() {
+        let plugin = ImportPlugin;
+        let mut resolver = crate::Resolver::new(vec![
+            &crate::plugins::BeforePlugin as &dyn crate::Plugin,
+            &crate::plugins::TemplatingPlugin as &dyn crate::Plugin,
+        ]);
+        let mut table_values = HashMap::new();
+
+        let temp_file = create_temp_toml_file(
+            r#"
+[base]
+domain = "example.com"
+
+[api]
+_.before = ["base"]
+host = "api.{{ domain }}"
+"#,
+        );
+        let file = temp_file.path().to_str().unwrap().to_string();
+
+        let config = Value::try_from(vec![ImportConfig {
+            file: Some(file.clone()),
+            table: Some("api".to_string()),
+            resolve: true,
+            ..Default::default()
+        }])
+        .unwrap();
+
+        plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap();
+
+        assert_eq!(
+            table_values.get("host").unwrap().as_str(),
+            Some("api.example.com")
+        );
+        assert_eq!(
+            table_values.get("domain").unwrap().as_str(),
+            Some("example.com")
+        );
+        assert!(!table_values.contains_key("_"));
+
+        // A table is needed to know what to resolve
+        let config = Value::try_from(vec![ImportConfig {
+            file: Some(file),
+            resolve: true,
+            ..Default::default()
+        }])
+        .unwrap();
+        let error = plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap_err();
+        assert!(error.to_string().contains("requires a table"));
+    }
+
+    #[test]
+    fn test_import_paths_relative_to_declaring_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("shared")).unwrap();
+        fs::write(
+            dir.path().join("app.toml"),
+            r#"
+[app]
+_.import = [{ file = "shared/db.toml", table = "db" }]
+"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("shared/db.toml"), "[db]\nhost = \"db\"\n").unwrap();
+
+        let mut resolver = crate::Resolver::new(vec![&ImportPlugin as &dyn crate::Plugin]);
+        let values = resolver
+            .resolve_table(dir.path().join("app.toml").to_str().unwrap(), "app")
+            .unwrap();
+
+        assert_eq!(values.get("host").unwrap().as_str(), Some("db"));
+        assert_eq!(resolver.base_dir.as_deref(), Some(dir.path()));
+    }
+
+    #[test]
+    fn test_import_plugin_optional_and_glob() {
+        let (plugin, mut resolver, mut table_values) = create_test_setup();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.toml"), "[app]\nname = \"b\"\n").unwrap();
+        fs::write(dir.path().join("a.toml"), "[app]\nname = \"a\"\nport = 1\n").unwrap();
+        let file = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+        let config = Value::try_from(vec![
+            ImportConfig {
+                file: Some(file("*.toml")),
+                table: Some("app".to_string()),
+                ..Default::default()
+            },
+            ImportConfig {
+                file: Some(file("missing.toml")),
+                table: Some("app".to_string()),
+                optional: true,
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+        plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap();
+
+        assert_eq!(table_values.get("name").unwrap().as_str(), Some("b"));
+        assert_eq!(table_values.get("port").unwrap().as_integer(), Some(1));
+        assert_eq!(resolver.notes.len(), 1);
+        assert!(resolver.notes[0].contains("missing.toml"));
+
+        // A pattern matching nothing is an error unless optional
+        let config = Value::try_from(vec![ImportConfig {
+            file: Some(file("*.json")),
+            ..Default::default()
+        }])
+        .unwrap();
+        let error = plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap_err();
+        assert!(error.to_string().contains("no files match"));
+    }
+
+    #[test]
+    fn test_import_plugin_collision_warning() {
+        let (plugin, mut resolver, mut table_values) = create_test_setup();
+        table_values.insert("host".to_string(), Value::String("local".to_string()));
+
+        let temp_file = create_temp_toml_file(
+            r#"
+[db]
+Host = "upper"
+host = "lower"
+"#,
+        );
+        let config = Value::try_from(vec![ImportConfig {
+            file: Some(temp_file.path().to_str().unwrap().to_string()),
+            table: Some("db".to_string()),
+            key_format: Some("{{ key | lower }}".to_string()),
+            on_collision: OnCollision::Warn,
+            ..Default::default()
+        }])
+        .unwrap();
+
+        plugin
+            .process(&mut resolver, &mut table_values, config)
+            .unwrap();
+
+        assert_eq!(table_values.get("host").unwrap().as_str(), Some("lower"));
+        assert_eq!(
+            resolver.warnings,
+            vec![
+                format!(
+                    "key 'Host' from '{}' is imported as 'host', which is already defined in the table",
+                    temp_file.path().display()
+                ),
+                format!(
+                    "keys 'Host' and 'host' from '{}' are both imported as 'host'",
+                    temp_file.path().display()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependencies_skip_only_malformed_entries() {
+        let config: Value = toml::from_str::<toml::Table>(
+            r#"
+import = [
+    { file = "mise.toml", table = "tools" },
+    { file = "other.toml", table = "db", include = "host" },
+    { env = "APP_" },
+    { optional = "yes" },
+]
+"#,
+        )
+        .unwrap()
+        .remove("import")
+        .unwrap();
+
+        let dependency = |file: &str, table: Option<&str>| crate::listing::Dependency {
+            directive: "import".to_string(),
+            table: table.map(str::to_string),
+            file: Some(file.to_string()),
+        };
+        assert_eq!(
+            ImportPlugin.dependencies(&config),
+            vec![
+                dependency("mise.toml", Some("tools")),
+                dependency("other.toml", Some("db")),
+                dependency("$APP_*", None),
+            ]
+        );
+    }
 }
@@ -7,7 +7,7 @@ pub mod templating;
 
 pub use after::AfterPlugin;
 pub use before::BeforePlugin;
-pub use import::{ImportConfig, ImportPlugin};
+pub use import::{ImportConfig, ImportPlugin, OnCollision};
 pub use noop::NoopPlugin;
 pub use reference::{ReferenceConfig, ReferencePlugin};
-pub use templating::TemplatingPlugin;
+pub use templating::{TemplatingConfig, TemplatingPlugin};
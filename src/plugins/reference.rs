@@ -32,6 +32,15 @@ impl Plugin for ReferencePlugin {
 
         Ok(())
     }
+
+    fn dependencies(&self, config: &toml::Value) -> Vec<crate::listing::Dependency> {
+        config
+            .get("table")
+            .and_then(|v| v.as_str())
+            .map(|table_name| crate::listing::Dependency::table(self.name(), table_name))
+            .into_iter()
+            .collect()
+    }
 }
 
 #[cfg(test)]
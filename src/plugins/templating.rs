@@ -1,36 +1,140 @@
 use crate::{
+    extract_config,
     utils::{
-        add_values_to_resolver, create_template_environment_with_meta, template_error,
-        toml_value_to_jinja,
+        add_values_to_resolver, create_template_environment_with_meta, jinja_value_to_toml,
+        template_error, toml_value_to_jinja,
     },
     Plugin, SuperTomlError,
 };
 use minijinja::Value as JinjaValue;
+use serde::Deserialize;
 use std::collections::HashMap;
 
+#[derive(Debug, Default, Deserialize)]
+pub struct TemplatingConfig {
+    /// Render values that consist of a single `{{ expr }}` as the
+    /// expression's native type instead of a string
+    #[serde(default)]
+    pub typed: bool,
+    /// Keys that always render as strings, even in typed mode
+    #[serde(default)]
+    pub strings: Vec<String>,
+}
+
 pub struct TemplatingPlugin;
 
-fn process_value_with_jinja(
-    value: &toml::Value,
+/// Return the inner expression if `s` is exactly one `{{ expr }}` block
+fn single_expression(s: &str) -> Option<&str> {
+    let inner = s.strip_prefix("{{")?.strip_suffix("}}")?;
+    let inner = inner.strip_prefix('-').unwrap_or(inner);
+    let inner = inner.strip_suffix('-').unwrap_or(inner);
+
+    if inner.contains("{{") || inner.contains("}}") || inner.contains("{%") {
+        return None;
+    }
+
+    Some(inner.trim())
+}
+
+/// A datetime handed to a typed expression, so that a result that is one of
+/// the given datetimes can be told apart from a string that looks like one
+#[derive(Debug)]
+struct DatetimeValue(toml::value::Datetime);
+
+impl std::fmt::Display for DatetimeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl minijinja::value::Object for DatetimeValue {}
+
+/// As `toml_value_to_jinja`, with datetimes kept as `DatetimeValue` objects
+fn toml_value_to_jinja_with_datetimes(value: &toml::Value) -> JinjaValue {
+    match value {
+        toml::Value::Datetime(dt) => JinjaValue::from_object(DatetimeValue(*dt)),
+        toml::Value::Array(arr) => JinjaValue::from(
+            arr.iter()
+                .map(toml_value_to_jinja_with_datetimes)
+                .collect::<Vec<_>>(),
+        ),
+        toml::Value::Table(table) => JinjaValue::from(
+            table
+                .iter()
+                .map(|(k, v)| (k.clone(), toml_value_to_jinja_with_datetimes(v)))
+                .collect::<HashMap<_, _>>(),
+        ),
+        other => toml_value_to_jinja(other),
+    }
+}
+
+/// Build the template context, with the `_` object from the meta values
+fn jinja_context(
     context: &HashMap<String, toml::Value>,
     meta_values: &HashMap<String, toml::Value>,
-    plugin_name: &str,
-) -> Result<toml::Value, SuperTomlError> {
-    let env = create_template_environment_with_meta(meta_values.clone());
-
+    convert: fn(&toml::Value) -> JinjaValue,
+) -> HashMap<String, JinjaValue> {
     let mut context_jinja: HashMap<String, JinjaValue> = context
         .iter()
-        .map(|(k, v)| (k.clone(), toml_value_to_jinja(v)))
+        .map(|(k, v)| (k.clone(), convert(v)))
         .collect();
 
     // Add the _ object to the template context (but exclude it from output)
     if let Some(underscore_value) = meta_values.get("_") {
-        context_jinja.insert("_".to_string(), toml_value_to_jinja(underscore_value));
+        context_jinja.insert("_".to_string(), convert(underscore_value));
     }
 
+    context_jinja
+}
+
+fn process_value_with_jinja(
+    value: &toml::Value,
+    context: &HashMap<String, toml::Value>,
+    meta_values: &HashMap<String, toml::Value>,
+    plugin_name: &str,
+    typed: bool,
+) -> Result<toml::Value, SuperTomlError> {
+    let env = create_template_environment_with_meta(meta_values.clone());
+
+    let context_jinja = jinja_context(context, meta_values, toml_value_to_jinja);
+
     match value {
         toml::Value::String(s) => {
-            if s.contains("{{") || s.contains("{%") || s.contains("{#") {
+            if let Some(expr) = single_expression(s).filter(|_| typed) {
+                let expression = env
+                    .compile_expression(expr)
+                    .map_err(|e| template_error(plugin_name, "Template error", e))?;
+
+                let result = expression
+                    .eval(&context_jinja)
+                    .map_err(|e| template_error(plugin_name, "Render error", e))?;
+
+                let value = jinja_value_to_toml(&result).map_err(|e| {
+                    template_error(
+                        plugin_name,
+                        "Render error",
+                        format!("expression '{}': {}", expr, e),
+                    )
+                })?;
+
+                // Jinja has no datetime type, so datetimes come back as
+                // strings. Evaluating again with datetimes as objects tells
+                // whether the result is one of them rather than a string
+                // that looks like one.
+                match value {
+                    toml::Value::String(s) if s.parse::<toml::value::Datetime>().is_ok() => {
+                        let with_datetimes =
+                            jinja_context(context, meta_values, toml_value_to_jinja_with_datetimes);
+                        let datetime = expression.eval(&with_datetimes).ok().and_then(|result| {
+                            result.downcast_object_ref::<DatetimeValue>().map(|dt| dt.0)
+                        });
+                        Ok(datetime
+                            .map(toml::Value::Datetime)
+                            .unwrap_or(toml::Value::String(s)))
+                    }
+                    other => Ok(other),
+                }
+            } else if s.contains("{{") || s.contains("{%") || s.contains("{#") {
                 let template = env
                     .template_from_str(s)
                     .map_err(|e| template_error(plugin_name, "Template error", e))?;
@@ -48,7 +152,9 @@ fn process_value_with_jinja(
             // Recursively process each element in the array
             let processed_arr: Result<Vec<toml::Value>, SuperTomlError> = arr
                 .iter()
-                .map(|item| process_value_with_jinja(item, context, meta_values, plugin_name))
+                .map(|item| {
+                    process_value_with_jinja(item, context, meta_values, plugin_name, typed)
+                })
                 .collect();
             Ok(toml::Value::Array(processed_arr?))
         }
@@ -57,7 +163,7 @@ fn process_value_with_jinja(
             let mut processed_table = toml::Table::new();
             for (key, val) in table {
                 let processed_val =
-                    process_value_with_jinja(val, context, meta_values, plugin_name)?;
+                    process_value_with_jinja(val, context, meta_values, plugin_name, typed)?;
                 processed_table.insert(key.clone(), processed_val);
             }
             Ok(toml::Value::Table(processed_table))
@@ -75,8 +181,10 @@ impl Plugin for TemplatingPlugin {
         &self,
         resolver: &mut crate::Resolver,
         table_values: &mut HashMap<String, toml::Value>,
-        _config: toml::Value,
+        config: toml::Value,
     ) -> Result<(), SuperTomlError> {
+        let config: TemplatingConfig = extract_config!(config, TemplatingConfig, self.name())?;
+
         let processed_values: HashMap<String, toml::Value> = table_values
             .iter()
             .map(|(key, value)| {
@@ -85,7 +193,9 @@ impl Plugin for TemplatingPlugin {
                     &resolver.values,
                     &resolver.meta_values,
                     self.name(),
-                )?;
+                    config.typed && !config.strings.contains(key),
+                )
+                .map_err(|e| e.with_key(key))?;
                 Ok((key.clone(), processed_value))
             })
             .collect::<Result<HashMap<_, _>, SuperTomlError>>()?;
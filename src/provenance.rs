@@ -0,0 +1,225 @@
+//! Tracking where each resolved value came from
+
+use crate::formatter::toml_value_to_json;
+use std::collections::HashMap;
+
+/// Where a value was defined and how it reached the resolved output
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueSource {
+    /// File that defined the value, if it came from a file
+    pub file: Option<String>,
+    /// Table that defined the value, if it came from a table
+    pub table: Option<String>,
+    /// Directive that brought the value in, such as `before` for a table
+    /// listed in `_.before` or `import` for an imported key. `None` for the
+    /// requested tables themselves
+    pub plugin: Option<String>,
+    /// The value as written, before templating
+    pub raw: toml::Value,
+    /// The value after processing
+    pub value: toml::Value,
+}
+
+impl ValueSource {
+    /// Whether two sources describe the same definition, ignoring processing
+    fn same_origin(&self, other: &ValueSource) -> bool {
+        self.file == other.file
+            && self.table == other.table
+            && self.plugin == other.plugin
+            && self.raw == other.raw
+    }
+
+    /// Describe the source, for example `global in app.toml via _.before`
+    pub fn describe(&self) -> String {
+        let mut description = match (&self.table, &self.file) {
+            (Some(table), Some(file)) => format!("{} in {}", table, file),
+            (Some(table), None) => table.clone(),
+            (None, Some(file)) => file.clone(),
+            (None, None) => "command line".to_string(),
+        };
+        if let Some(plugin) = &self.plugin {
+            if plugin.starts_with('-') {
+                description.push_str(&format!(" via {}", plugin));
+            } else {
+                description.push_str(&format!(" via _.{}", plugin));
+            }
+        }
+        description
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "file": self.file,
+            "table": self.table,
+            "plugin": self.plugin,
+            "raw": toml_value_to_json(&self.raw),
+            "value": toml_value_to_json(&self.value),
+        })
+    }
+}
+
+/// The source of a resolved value along with the values it replaced, oldest
+/// first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    pub source: ValueSource,
+    pub shadowed: Vec<ValueSource>,
+}
+
+impl Provenance {
+    /// Record a new value, keeping the previous one as shadowed unless it
+    /// comes from the same definition (a table propagating its values again
+    /// after templating, or a table resolved twice)
+    pub fn update(&mut self, source: ValueSource) {
+        if self.source.same_origin(&source) {
+            self.source = source;
+        } else {
+            let previous = std::mem::replace(&mut self.source, source);
+            self.shadowed.push(previous);
+        }
+    }
+}
+
+/// Record `source` as the latest value of `key`
+pub fn record(provenance: &mut HashMap<String, Provenance>, key: &str, source: ValueSource) {
+    match provenance.get_mut(key) {
+        Some(existing) => existing.update(source),
+        None => {
+            provenance.insert(
+                key.to_string(),
+                Provenance {
+                    source,
+                    shadowed: Vec::new(),
+                },
+            );
+        }
+    }
+}
+
+/// Render provenance as text, one block per key in sorted order
+pub fn format_provenance(provenance: &HashMap<String, Provenance>, keys: &[&String]) -> String {
+    let mut blocks = Vec::new();
+    for key in keys {
+        let Some(entry) = provenance.get(*key) else {
+            blocks.push(format!("{} (no recorded source)", key));
+            continue;
+        };
+
+        let mut lines = vec![
+            format!("{} = {}", key, entry.source.value),
+            format!("  from {}", entry.source.describe()),
+        ];
+        if entry.source.raw != entry.source.value {
+            lines.push(format!("  raw {}", entry.source.raw));
+        }
+        if !entry.shadowed.is_empty() {
+            lines.push("  shadowed".to_string());
+            for shadowed in entry.shadowed.iter().rev() {
+                lines.push(format!(
+                    "    {} from {}",
+                    shadowed.value,
+                    shadowed.describe()
+                ));
+            }
+        }
+        blocks.push(lines.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+/// Render provenance as a JSON object keyed by value name
+pub fn provenance_to_json(
+    provenance: &HashMap<String, Provenance>,
+    keys: &[&String],
+) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    for key in keys {
+        if let Some(entry) = provenance.get(*key) {
+            let mut source = entry.source.to_json();
+            source["shadowed"] =
+                serde_json::Value::Array(entry.shadowed.iter().map(ValueSource::to_json).collect());
+            map.insert(key.to_string(), source);
+        }
+    }
+    serde_json::Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Plugin;
+    use std::fs;
+
+    fn source(table: &str, plugin: Option<&str>, value: i64) -> ValueSource {
+        ValueSource {
+            file: Some("app.toml".to_string()),
+            table: Some(table.to_string()),
+            plugin: plugin.map(|p| p.to_string()),
+            raw: toml::Value::Integer(value),
+            value: toml::Value::Integer(value),
+        }
+    }
+
+    #[test]
+    fn test_record_keeps_shadowed_values() {
+        let mut provenance = HashMap::new();
+        record(
+            &mut provenance,
+            "port",
+            source("global", Some("before"), 80),
+        );
+        record(
+            &mut provenance,
+            "port",
+            source("global", Some("before"), 80),
+        );
+        record(&mut provenance, "port", source("prod", None, 443));
+
+        let entry = &provenance["port"];
+        assert_eq!(entry.source, source("prod", None, 443));
+        assert_eq!(entry.shadowed, vec![source("global", Some("before"), 80)]);
+        assert_eq!(
+            entry.shadowed[0].describe(),
+            "global in app.toml via _.before"
+        );
+    }
+
+    #[test]
+    fn test_resolver_tracks_provenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        fs::write(
+            &path,
+            r#"
+[global]
+port = 80
+name = "app"
+
+[prod]
+_.before = ["global"]
+port = 443
+url = "{{ name }}:{{ port }}"
+"#,
+        )
+        .unwrap();
+
+        let mut resolver = crate::Resolver::new(vec![
+            &crate::plugins::BeforePlugin as &dyn Plugin,
+            &crate::plugins::TemplatingPlugin as &dyn Plugin,
+        ]);
+        let file = path.to_str().unwrap();
+        resolver.resolve_table(file, "prod").unwrap();
+
+        let port = &resolver.provenance["port"];
+        assert_eq!(port.source.table.as_deref(), Some("prod"));
+        assert_eq!(port.source.plugin, None);
+        assert_eq!(port.shadowed.len(), 1);
+        assert_eq!(port.shadowed[0].table.as_deref(), Some("global"));
+        assert_eq!(port.shadowed[0].plugin.as_deref(), Some("before"));
+
+        let url = &resolver.provenance["url"];
+        assert_eq!(url.source.raw.as_str(), Some("{{ name }}:{{ port }}"));
+        assert_eq!(url.source.value.as_str(), Some("app:443"));
+        assert!(url.shadowed.is_empty());
+    }
+}
@@ -0,0 +1,434 @@
+//! Rendering several outputs from one file, driven by the root `_.outputs`
+//! directive
+
+use crate::error::SuperTomlError;
+use crate::formatter::{format_values, OutputFormat};
+use crate::overrides::{resolve_with_overrides, Override, OverrideMode};
+use crate::Resolver;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One entry of `_.outputs`: the tables to resolve, the format and the file
+/// to write
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputTarget {
+    pub tables: Vec<String>,
+    pub format: OutputFormat,
+    pub path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TableSelection {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOutputTarget {
+    table: TableSelection,
+    format: OutputFormat,
+    path: String,
+}
+
+/// Rendered file content waiting to be written
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedOutput {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Read the `_.outputs` list from the root of a TOML document
+pub fn output_targets(toml: &toml::Value) -> Result<Vec<OutputTarget>, SuperTomlError> {
+    let outputs = toml
+        .get("_")
+        .and_then(|directives| directives.get("outputs"))
+        .ok_or_else(|| SuperTomlError::InvalidOutputs("no _.outputs in file".to_string()))?;
+
+    let raw: Vec<RawOutputTarget> = outputs
+        .clone()
+        .try_into()
+        .map_err(|e: toml::de::Error| SuperTomlError::InvalidOutputs(e.message().to_string()))?;
+
+    raw.into_iter()
+        .map(|target| {
+            let tables = match target.table {
+                TableSelection::One(table) => vec![table],
+                TableSelection::Many(tables) => tables,
+            };
+            if tables.is_empty() {
+                return Err(SuperTomlError::InvalidOutputs(format!(
+                    "no tables given for '{}'",
+                    target.path
+                )));
+            }
+            Ok(OutputTarget {
+                tables,
+                format: target.format,
+                path: target.path,
+            })
+        })
+        .collect()
+}
+
+/// Resolve and format every target with the resolver's loaded file
+///
+/// Nothing is written; relative paths are resolved against `base_dir`.
+pub fn render_outputs(
+    resolver: &mut Resolver,
+    targets: &[OutputTarget],
+    base_dir: &Path,
+    overrides: &[Override],
+    mode: OverrideMode,
+) -> Result<Vec<RenderedOutput>, SuperTomlError> {
+    let mut rendered: Vec<RenderedOutput> = Vec::new();
+
+    for target in targets {
+        let path = base_dir.join(&target.path);
+        if rendered.iter().any(|output| output.path == path) {
+            return Err(SuperTomlError::InvalidOutputs(format!(
+                "'{}' is listed more than once",
+                target.path
+            )));
+        }
+
+        let tables: Vec<&str> = target.tables.iter().map(String::as_str).collect();
+        let values = resolve_with_overrides(
+            resolver,
+            &tables,
+            Some(&target.format.to_string()),
+            overrides,
+            mode,
+        )?;
+
+        let mut content = format_values(&values, target.format)?;
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        rendered.push(RenderedOutput { path, content });
+    }
+
+    Ok(rendered)
+}
+
+/// Write rendered outputs so that no destination is changed unless every
+/// output could be written
+///
+/// Each file is first written to a temporary file next to its destination.
+/// Only when all of them succeed are they renamed into place, moving any
+/// existing destination aside first. If a rename fails, the destinations
+/// already replaced are put back, and the temporary files and any
+/// directories created for the outputs are removed.
+pub fn write_outputs(outputs: &[RenderedOutput]) -> Result<(), SuperTomlError> {
+    let mut created_dirs: Vec<PathBuf> = Vec::new();
+    let mut staged: Vec<StagedOutput> = Vec::new();
+
+    for output in outputs {
+        match stage_output(output, &mut created_dirs) {
+            Ok(temp_path) => staged.push(StagedOutput {
+                temp_path,
+                destination: &output.path,
+            }),
+            Err(e) => {
+                remove_staged(&staged);
+                remove_dirs(&created_dirs);
+                return Err(e);
+            }
+        }
+    }
+
+    install(&staged).inspect_err(|_| remove_dirs(&created_dirs))
+}
+
+/// An output written to a temporary file, waiting to be renamed into place
+struct StagedOutput<'a> {
+    temp_path: PathBuf,
+    destination: &'a Path,
+}
+
+/// A destination that has been replaced, with where its previous content
+/// was moved to if it existed
+struct Installed<'a> {
+    destination: &'a Path,
+    backup: Option<PathBuf>,
+}
+
+fn stage_output(
+    output: &RenderedOutput,
+    created_dirs: &mut Vec<PathBuf>,
+) -> Result<PathBuf, SuperTomlError> {
+    let write_error = |error| SuperTomlError::OutputWrite {
+        path: output.path.display().to_string(),
+        error,
+    };
+
+    if output.path.is_dir() {
+        return Err(write_error(std::io::Error::other(
+            "destination is a directory",
+        )));
+    }
+
+    let temp_path = sibling_path(&output.path, "tmp").ok_or_else(|| {
+        write_error(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "destination has no file name",
+        ))
+    })?;
+
+    let parent = output.path.parent().unwrap_or(Path::new(""));
+    let missing: Vec<PathBuf> = parent
+        .ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    if !missing.is_empty() {
+        fs::create_dir_all(parent).map_err(write_error)?;
+        created_dirs.extend(missing.into_iter().rev());
+    }
+
+    fs::write(&temp_path, &output.content).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        write_error(e)
+    })?;
+
+    Ok(temp_path)
+}
+
+/// A hidden file next to `path`, such as `.config.json.1234.tmp`
+fn sibling_path(path: &Path, suffix: &str) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let parent = path.parent().unwrap_or(Path::new(""));
+    Some(parent.join(format!(".{}.{}.{}", file_name, std::process::id(), suffix)))
+}
+
+/// Rename staged files into place, putting back the destinations already
+/// replaced if one of them fails
+fn install(staged: &[StagedOutput]) -> Result<(), SuperTomlError> {
+    let mut installed: Vec<Installed> = Vec::new();
+
+    for (index, output) in staged.iter().enumerate() {
+        match install_output(output) {
+            Ok(done) => installed.push(done),
+            Err(error) => {
+                roll_back(&installed);
+                remove_staged(&staged[index..]);
+                return Err(SuperTomlError::OutputWrite {
+                    path: output.destination.display().to_string(),
+                    error,
+                });
+            }
+        }
+    }
+
+    for done in installed {
+        if let Some(backup) = done.backup {
+            let _ = fs::remove_file(backup);
+        }
+    }
+    Ok(())
+}
+
+fn install_output<'a>(output: &StagedOutput<'a>) -> std::io::Result<Installed<'a>> {
+    let backup = match sibling_path(output.destination, "bak") {
+        Some(backup) if output.destination.exists() => {
+            fs::rename(output.destination, &backup)?;
+            Some(backup)
+        }
+        _ => None,
+    };
+
+    if let Err(e) = fs::rename(&output.temp_path, output.destination) {
+        if let Some(backup) = &backup {
+            let _ = fs::rename(backup, output.destination);
+        }
+        return Err(e);
+    }
+
+    Ok(Installed {
+        destination: output.destination,
+        backup,
+    })
+}
+
+/// Undo installed outputs, newest first
+fn roll_back(installed: &[Installed]) {
+    for done in installed.iter().rev() {
+        match &done.backup {
+            Some(backup) => {
+                let _ = fs::rename(backup, done.destination);
+            }
+            None => {
+                let _ = fs::remove_file(done.destination);
+            }
+        }
+    }
+}
+
+fn remove_staged(staged: &[StagedOutput]) {
+    for output in staged {
+        let _ = fs::remove_file(&output.temp_path);
+    }
+}
+
+/// Remove directories created for outputs, deepest first, if they are empty
+fn remove_dirs(created_dirs: &[PathBuf]) {
+    for dir in created_dirs.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_targets() {
+        let toml: toml::Value = toml::from_str(
+            r#"
+_.outputs = [
+    { table = "app", format = "dotenv", path = ".env" },
+    { table = ["base", "prod"], format = "tfvars", path = "tf/prod.tfvars" },
+]
+
+[app]
+name = "x"
+"#,
+        )
+        .unwrap();
+
+        let targets = output_targets(&toml).unwrap();
+        assert_eq!(
+            targets,
+            vec![
+                OutputTarget {
+                    tables: vec!["app".to_string()],
+                    format: OutputFormat::Dotenv,
+                    path: ".env".to_string(),
+                },
+                OutputTarget {
+                    tables: vec!["base".to_string(), "prod".to_string()],
+                    format: OutputFormat::Tfvars,
+                    path: "tf/prod.tfvars".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_targets_invalid() {
+        let missing: toml::Value = toml::from_str("[app]\nname = 'x'").unwrap();
+        assert!(matches!(
+            output_targets(&missing),
+            Err(SuperTomlError::InvalidOutputs(_))
+        ));
+
+        let bad_format: toml::Value =
+            toml::from_str(r#"_.outputs = [{ table = "app", format = "xml", path = "a" }]"#)
+                .unwrap();
+        assert!(matches!(
+            output_targets(&bad_format),
+            Err(SuperTomlError::InvalidOutputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_outputs_leaves_destinations_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing.env");
+        fs::write(&existing, "old\n").unwrap();
+        fs::create_dir(dir.path().join("blocked")).unwrap();
+
+        let result = write_outputs(&[
+            RenderedOutput {
+                path: existing.clone(),
+                content: "new\n".to_string(),
+            },
+            RenderedOutput {
+                path: dir.path().join("blocked"),
+                content: "x\n".to_string(),
+            },
+        ]);
+
+        assert!(matches!(result, Err(SuperTomlError::OutputWrite { .. })));
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old\n");
+        let mut entries: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["blocked", "existing.env"]);
+    }
+
+    #[test]
+    fn test_write_outputs_removes_created_directories_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("blocked")).unwrap();
+
+        let result = write_outputs(&[
+            RenderedOutput {
+                path: dir.path().join("new/nested/config.json"),
+                content: "{}\n".to_string(),
+            },
+            RenderedOutput {
+                path: dir.path().join("blocked"),
+                content: "x\n".to_string(),
+            },
+        ]);
+
+        assert!(result.is_err());
+        assert!(!dir.path().join("new").exists());
+    }
+
+    #[test]
+    fn test_install_restores_replaced_destinations() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing.env");
+        let created = dir.path().join("created.env");
+        fs::write(&existing, "old\n").unwrap();
+        let staged_existing = dir.path().join(".existing.env.tmp");
+        let staged_created = dir.path().join(".created.env.tmp");
+        fs::write(&staged_existing, "new\n").unwrap();
+        fs::write(&staged_created, "new\n").unwrap();
+        let failing = dir.path().join("failing.env");
+
+        // The last temporary file is missing, so its rename fails
+        let result = install(&[
+            StagedOutput {
+                temp_path: staged_existing,
+                destination: &existing,
+            },
+            StagedOutput {
+                temp_path: staged_created,
+                destination: &created,
+            },
+            StagedOutput {
+                temp_path: dir.path().join(".failing.env.tmp"),
+                destination: &failing,
+            },
+        ]);
+
+        assert!(matches!(result, Err(SuperTomlError::OutputWrite { .. })));
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old\n");
+        let entries: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["existing.env"]);
+    }
+
+    #[test]
+    fn test_write_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("out/config.json");
+
+        write_outputs(&[RenderedOutput {
+            path: nested.clone(),
+            content: "{}\n".to_string(),
+        }])
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&nested).unwrap(), "{}\n");
+    }
+}
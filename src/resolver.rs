@@ -1,6 +1,10 @@
 use crate::error::SuperTomlError;
-use crate::loader::{load_toml_file, TomlTable};
-use std::collections::HashMap;
+use crate::listing::Dependency;
+use crate::loader::{find_span, find_table, load_toml_source, parse_table_path, TomlTable};
+use crate::merge::{MergeConfig, MergeStrategy};
+use crate::provenance::{self, Provenance, ValueSource};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[macro_export]
 macro_rules! extract_config {
@@ -31,15 +35,66 @@ pub trait Plugin {
         table_values: &mut HashMap<String, toml::Value>,
         config: toml::Value,
     ) -> Result<(), SuperTomlError>;
+
+    /// Tables or files this plugin's directive pulls in, used for listing
+    fn dependencies(&self, _config: &toml::Value) -> Vec<Dependency> {
+        Vec::new()
+    }
+}
+
+/// A table being resolved, the file it is in and the directive that pulled
+/// it in (`None` for a requested table)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub table: String,
+    pub file: Option<String>,
+    pub via: Option<String>,
+}
+
+/// State kept for each table on the call stack
+#[derive(Default)]
+struct TableFrame {
+    /// Sources of the table's keys
+    origins: HashMap<String, ValueSource>,
+    merge: MergeStrategy,
+    /// Values the table's keys had before the table first set them, which
+    /// every later write of the key by the table is merged into
+    bases: HashMap<String, Option<toml::Value>>,
+    /// Keys that came from `_.extends` rather than the table itself
+    inherited: HashSet<String>,
 }
 
 pub struct Resolver {
     pub plugins: Vec<&'static dyn Plugin>,
     pub values: HashMap<String, toml::Value>,
-    pub call_stack: Vec<String>,
+    pub call_stack: Vec<CallFrame>,
     pub toml_file: Option<toml::Value>,
     pub file_path: Option<String>,
+    /// Source text of the loaded file, used to locate errors
+    pub source: Option<String>,
+    /// Directory of the loaded file, which its import paths are relative to;
+    /// the working directory when unset
+    pub base_dir: Option<PathBuf>,
     pub meta_values: HashMap<String, toml::Value>,
+    /// Plugin whose directive is currently being processed
+    pub current_plugin: Option<String>,
+    /// Where each value in `values` came from
+    pub provenance: HashMap<String, Provenance>,
+    /// Whether imports may run commands
+    pub allow_exec: bool,
+    /// Things worth knowing about the resolution that are not errors, such
+    /// as skipped optional imports
+    pub notes: Vec<String>,
+    /// Problems that did not stop resolution, such as imported keys
+    /// colliding with `on_collision = "warn"`, kept across resolutions
+    pub warnings: Vec<String>,
+    /// How values replace values set by other tables, unless the file's
+    /// root `_.merge` or a table's own says otherwise
+    pub merge: MergeStrategy,
+    frames: Vec<TableFrame>,
+    /// Keys set while resolving the parents of each table being extended,
+    /// innermost last
+    extending: Vec<HashSet<String>>,
 }
 
 impl Resolver {
@@ -50,7 +105,74 @@ impl Resolver {
             call_stack: Vec::new(),
             toml_file: None,
             file_path: None,
+            source: None,
+            base_dir: None,
             meta_values: HashMap::new(),
+            current_plugin: None,
+            provenance: HashMap::new(),
+            allow_exec: false,
+            notes: Vec::new(),
+            warnings: Vec::new(),
+            merge: MergeStrategy::default(),
+            frames: Vec::new(),
+            extending: Vec::new(),
+        }
+    }
+
+    /// Set a resolved value, recording its source for the table being
+    /// processed
+    ///
+    /// The value is merged into the one it replaces with the table's merge
+    /// strategy.
+    pub fn set_value(&mut self, key: &str, value: toml::Value) {
+        let Some(frame) = self.frames.last_mut() else {
+            self.values.insert(key.to_string(), value);
+            return;
+        };
+
+        // Plugins set the table's values repeatedly as they process them, so
+        // merge into the value from before the table rather than the current.
+        // Parents of an extended table only merge into values they set
+        // themselves, leaving earlier values to be merged into by the table
+        let extending = self.extending.last();
+        let base = frame
+            .bases
+            .entry(key.to_string())
+            .or_insert_with(|| match extending {
+                Some(written) if !written.contains(key) => None,
+                _ => self.values.get(key).cloned(),
+            });
+        let value = match base {
+            Some(base) => frame.merge.merge(base, value),
+            None => value,
+        };
+
+        if let Some(source) = frame.origins.get(key) {
+            let source = ValueSource {
+                value: value.clone(),
+                ..source.clone()
+            };
+            provenance::record(&mut self.provenance, key, source);
+        }
+        if let Some(written) = self.extending.last_mut() {
+            written.insert(key.to_string());
+        }
+        self.values.insert(key.to_string(), value);
+    }
+
+    /// Whether a key of the table being processed was inherited through
+    /// `_.extends` rather than written in the table itself
+    pub fn is_inherited(&self, key: &str) -> bool {
+        self.frames
+            .last()
+            .is_some_and(|frame| frame.inherited.contains(key))
+    }
+
+    /// Record where a key of the table being processed came from, for keys
+    /// that were not written in the table itself (such as imported keys)
+    pub fn record_origin(&mut self, key: &str, source: ValueSource) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.origins.insert(key.to_string(), source);
         }
     }
 
@@ -59,28 +181,7 @@ impl Resolver {
         file_path: &str,
         table_name: &str,
     ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
-        self.file_path = Some(file_path.to_string());
-        self.toml_file = Some(load_toml_file(file_path)?);
-
-        // Populate meta values with processing context as nested TOML structure
-        let mut args_map = toml::map::Map::new();
-        args_map.insert(
-            "file_path".to_string(),
-            toml::Value::String(file_path.to_string()),
-        );
-        args_map.insert(
-            "table_name".to_string(),
-            toml::Value::String(table_name.to_string()),
-        );
-
-        let mut underscore_map = toml::map::Map::new();
-        underscore_map.insert("args".to_string(), toml::Value::Table(args_map));
-
-        self.meta_values
-            .insert("_".to_string(), toml::Value::Table(underscore_map));
-
-        resolve_table_recursive(self, table_name)?;
-        Ok(std::mem::take(&mut self.values))
+        self.resolve_tables_internal(file_path, &[table_name], None)
     }
 
     pub fn resolve_table_with_meta(
@@ -89,23 +190,81 @@ impl Resolver {
         table_name: &str,
         output_format: &str,
     ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+        self.resolve_tables_internal(file_path, &[table_name], Some(output_format))
+    }
+
+    /// Resolve several tables in order, layering their values so that keys
+    /// from later tables win
+    pub fn resolve_tables(
+        &mut self,
+        file_path: &str,
+        table_names: &[&str],
+    ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+        self.resolve_tables_internal(file_path, table_names, None)
+    }
+
+    pub fn resolve_tables_with_meta(
+        &mut self,
+        file_path: &str,
+        table_names: &[&str],
+        output_format: &str,
+    ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+        self.resolve_tables_internal(file_path, table_names, Some(output_format))
+    }
+
+    /// Load a file so that several sets of tables can be resolved from it
+    /// with [`Resolver::resolve_loaded_tables`] without parsing it again
+    pub fn load_file(&mut self, file_path: &str) -> Result<(), SuperTomlError> {
+        let (source, toml_file) = load_toml_source(file_path)?;
+        self.toml_file = Some(toml_file);
+        self.source = Some(source);
         self.file_path = Some(file_path.to_string());
-        self.toml_file = Some(load_toml_file(file_path)?);
+        self.base_dir = Path::new(file_path).parent().map(Path::to_path_buf);
+        Ok(())
+    }
+
+    /// Resolve tables from the file loaded by [`Resolver::load_file`]
+    pub fn resolve_loaded_tables(
+        &mut self,
+        table_names: &[&str],
+        output_format: Option<&str>,
+    ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+        if table_names.is_empty() {
+            return Err(SuperTomlError::TableNotFound(
+                "No table names given".to_string(),
+            ));
+        }
+
+        let file_path = self.file_path.clone().unwrap_or_default();
+
+        // Keep provenance only for values seeded before resolution
+        let values = &self.values;
+        self.provenance.retain(|key, _| values.contains_key(key));
+        self.notes.clear();
 
         // Populate meta values with processing context as nested TOML structure
+        let names: Vec<toml::Value> = table_names
+            .iter()
+            .map(|name| toml::Value::String(name.to_string()))
+            .collect();
+
         let mut args_map = toml::map::Map::new();
-        args_map.insert(
-            "file_path".to_string(),
-            toml::Value::String(file_path.to_string()),
-        );
+        args_map.insert("file_path".to_string(), toml::Value::String(file_path));
+        // A single table stays a plain string so existing templates keep working
         args_map.insert(
             "table_name".to_string(),
-            toml::Value::String(table_name.to_string()),
-        );
-        args_map.insert(
-            "output_format".to_string(),
-            toml::Value::String(output_format.to_string()),
+            match names.as_slice() {
+                [single] => single.clone(),
+                _ => toml::Value::Array(names.clone()),
+            },
         );
+        args_map.insert("table_names".to_string(), toml::Value::Array(names));
+        if let Some(output_format) = output_format {
+            args_map.insert(
+                "output_format".to_string(),
+                toml::Value::String(output_format.to_string()),
+            );
+        }
 
         let mut underscore_map = toml::map::Map::new();
         underscore_map.insert("args".to_string(), toml::Value::Table(args_map));
@@ -113,41 +272,226 @@ impl Resolver {
         self.meta_values
             .insert("_".to_string(), toml::Value::Table(underscore_map));
 
-        resolve_table_recursive(self, table_name)?;
+        for table_name in table_names {
+            resolve_table_recursive(self, table_name).map_err(|e| {
+                let file = self.file_path.clone();
+                e.with_context(|context| {
+                    if context.file.is_none() {
+                        context.file = file;
+                    }
+                })
+            })?;
+        }
         Ok(std::mem::take(&mut self.values))
     }
+
+    fn resolve_tables_internal(
+        &mut self,
+        file_path: &str,
+        table_names: &[&str],
+        output_format: Option<&str>,
+    ) -> Result<HashMap<String, toml::Value>, SuperTomlError> {
+        self.load_file(file_path)?;
+        self.resolve_loaded_tables(table_names, output_format)
+    }
 }
 
 pub fn resolve_table_recursive(
     resolver: &mut Resolver,
     table_name: &str,
 ) -> Result<(), SuperTomlError> {
+    let (table_path, table) = get_table_from_loaded_file(resolver, table_name)?;
+
+    // The directive that pulled this table in, if any
+    let via = resolver.current_plugin.clone();
+    let file = resolver.file_path.clone();
+    let frame = CallFrame {
+        table: table_path.clone(),
+        file: file.clone(),
+        via: via.clone(),
+    };
+
     // Check if we're currently processing this table (cycle detection)
-    if resolver.call_stack.contains(&table_name.to_string()) {
-        return Err(SuperTomlError::CycleDetected(table_name.to_string()));
+    if let Some(start) = resolver
+        .call_stack
+        .iter()
+        .position(|f| f.table == frame.table && f.file == frame.file)
+    {
+        let mut cycle = resolver.call_stack[start..].to_vec();
+        cycle.push(frame);
+        return Err(SuperTomlError::CycleDetected {
+            table: table_path,
+            cycle,
+        });
     }
 
+    let merge = merge_strategy(resolver, &table_path, &table)?;
+
     // Add to call stack for cycle detection
-    resolver.call_stack.push(table_name.to_string());
+    resolver.call_stack.push(frame);
+    resolver.frames.push(TableFrame {
+        merge,
+        ..TableFrame::default()
+    });
 
-    let table = get_table_from_loaded_file(resolver, table_name)?;
+    // Start from the values of the tables this one extends
+    let mut inherited = match table.get("_").and_then(|d| d.get("extends")) {
+        Some(config) => {
+            resolve_parents(resolver, config).map_err(|e| locate_error(resolver, e, "extends"))?
+        }
+        None => HashMap::new(),
+    };
 
     let mut table_values: HashMap<String, toml::Value> = HashMap::new();
+    let mut origins: HashMap<String, ValueSource> = HashMap::new();
     for (key, value) in &table {
         if key != "_" {
-            table_values.insert(key.clone(), value.clone());
+            let merged = match inherited.remove(key) {
+                Some((parent_value, parent_source)) => {
+                    provenance::record(&mut resolver.provenance, key, parent_source);
+                    merge.merge(&parent_value, value.clone())
+                }
+                None => value.clone(),
+            };
+            table_values.insert(key.clone(), merged.clone());
+            origins.insert(
+                key.clone(),
+                ValueSource {
+                    file: file.clone(),
+                    table: Some(table_path.clone()),
+                    plugin: via.clone(),
+                    raw: value.clone(),
+                    value: merged,
+                },
+            );
         }
     }
+    let inherited_keys: HashSet<String> = inherited.keys().cloned().collect();
+    for (key, (value, source)) in inherited {
+        table_values.insert(key.clone(), value);
+        origins.insert(key, source);
+    }
+    if let Some(current) = resolver.frames.last_mut() {
+        current.origins = origins;
+        current.inherited = inherited_keys;
+    }
 
     let plugins_table = table.get("_").and_then(|v| v.as_table());
     process_plugins(resolver, &mut table_values, plugins_table)?;
 
     // Remove from call stack
+    resolver.frames.pop();
     resolver.call_stack.pop();
+    resolver.current_plugin = via;
 
     Ok(())
 }
 
+/// Resolve the tables named by `_.extends` in order, returning the values
+/// they set with their sources
+///
+/// Neither the parents' values nor their provenance are kept in the
+/// resolver.
+fn resolve_parents(
+    resolver: &mut Resolver,
+    config: &toml::Value,
+) -> Result<HashMap<String, (toml::Value, ValueSource)>, SuperTomlError> {
+    let parents: Vec<&str> = match config {
+        toml::Value::String(parent) => vec![parent.as_str()],
+        toml::Value::Array(parents) => parents
+            .iter()
+            .map(|parent| parent.as_str())
+            .collect::<Option<_>>()
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    if parents.is_empty() {
+        return Err(SuperTomlError::PluginDeserialization {
+            plugin_name: "extends".to_string(),
+            error: "expected a table name or a non-empty list of table names".to_string(),
+        });
+    }
+
+    // Parents see the values resolved so far in templates, but only merge
+    // into values they set, and are rolled back afterwards
+    let values = resolver.values.clone();
+    let provenance = resolver.provenance.clone();
+    let via = resolver.current_plugin.replace("extends".to_string());
+    resolver.extending.push(HashSet::new());
+
+    let result = parents
+        .iter()
+        .try_for_each(|parent| resolve_table_recursive(resolver, parent));
+
+    resolver.current_plugin = via;
+    let written = resolver.extending.pop().unwrap_or_default();
+    let resolved = std::mem::replace(&mut resolver.values, values);
+    let mut inherited_provenance = std::mem::replace(&mut resolver.provenance, provenance);
+    result?;
+
+    Ok(resolved
+        .into_iter()
+        .filter(|(key, _)| written.contains(key))
+        .map(|(key, value)| {
+            let source = match inherited_provenance.remove(&key) {
+                Some(entry) => ValueSource {
+                    plugin: Some("extends".to_string()),
+                    value: value.clone(),
+                    ..entry.source
+                },
+                None => ValueSource {
+                    file: resolver.file_path.clone(),
+                    table: None,
+                    plugin: Some("extends".to_string()),
+                    raw: value.clone(),
+                    value: value.clone(),
+                },
+            };
+            (key, (value, source))
+        })
+        .collect())
+}
+
+/// The merge strategy for a table's values: the resolver's, overridden by
+/// the file's root `_.merge`, then by the table's own
+fn merge_strategy(
+    resolver: &Resolver,
+    table_path: &str,
+    table: &TomlTable,
+) -> Result<MergeStrategy, SuperTomlError> {
+    let root = resolver.toml_file.as_ref().and_then(|f| f.as_table());
+    let mut strategy = resolver.merge;
+
+    for (path, directives) in [("", root), (table_path, Some(table))] {
+        let Some(config) = directives
+            .and_then(|t| t.get("_"))
+            .and_then(|d| d.get("merge"))
+        else {
+            continue;
+        };
+        let config = MergeConfig::from_value(config.clone()).map_err(|e| {
+            let segments = parse_table_path(path).unwrap_or_default();
+            let key_path = ["_".to_string(), "merge".to_string()];
+            e.with_context(|context| {
+                context.call_stack = resolver
+                    .call_stack
+                    .iter()
+                    .map(|frame| frame.table.clone())
+                    .chain(std::iter::once(table_path.to_string()))
+                    .collect();
+                context.file = resolver.file_path.clone();
+                context.span = resolver
+                    .source
+                    .as_deref()
+                    .and_then(|source| find_span(source, &segments, &key_path));
+            })
+        })?;
+        strategy = strategy.overlay(&config);
+    }
+
+    Ok(strategy)
+}
+
 fn process_plugins(
     resolver: &mut Resolver,
     table_values: &mut HashMap<String, toml::Value>,
@@ -167,25 +511,63 @@ fn process_plugins(
             toml::Value::Table(TomlTable::new())
         };
 
+        resolver.current_plugin = Some(plugin_name.clone());
         plugin
             .process(resolver, table_values, config)
-            .map_err(|e| match e {
-                SuperTomlError::PluginError { .. }
-                | SuperTomlError::PluginDeserialization { .. } => e,
-                other => SuperTomlError::PluginError {
-                    plugin_name: plugin_name.to_string(),
-                    error: format!("{}", other),
-                },
-            })?;
+            .map_err(|e| locate_error(resolver, e, &plugin_name))?;
     }
 
     Ok(())
 }
 
+/// Wrap an error from a plugin with where it happened
+///
+/// Errors from nested tables already carry their location and are passed
+/// through. Otherwise the error points at the offending key if the plugin
+/// reported one, or at the plugin's directive.
+fn locate_error(resolver: &Resolver, error: SuperTomlError, plugin_name: &str) -> SuperTomlError {
+    let error = error.map_root(|root| match root {
+        SuperTomlError::PluginError { .. }
+        | SuperTomlError::PluginDeserialization { .. }
+        | SuperTomlError::CycleDetected { .. }
+        | SuperTomlError::CommandFailed { .. } => root,
+        other => SuperTomlError::PluginError {
+            plugin_name: plugin_name.to_string(),
+            error: other.message(),
+        },
+    });
+
+    error.with_context(|context| {
+        if context.call_stack.is_empty() {
+            context.call_stack = resolver
+                .call_stack
+                .iter()
+                .map(|frame| frame.table.clone())
+                .collect();
+        }
+        if context.file.is_none() {
+            context.file = resolver.file_path.clone();
+            let table_path = resolver
+                .call_stack
+                .last()
+                .and_then(|frame| parse_table_path(&frame.table).ok())
+                .unwrap_or_default();
+            let key_path = match &context.key {
+                Some(key) => vec![key.clone()],
+                None => vec!["_".to_string(), plugin_name.to_string()],
+            };
+            context.span = resolver
+                .source
+                .as_deref()
+                .and_then(|source| find_span(source, &table_path, &key_path));
+        }
+    })
+}
+
 fn get_table_from_loaded_file(
     resolver: &Resolver,
     table_name: &str,
-) -> Result<TomlTable, SuperTomlError> {
+) -> Result<(String, TomlTable), SuperTomlError> {
     let toml_file = resolver
         .toml_file
         .as_ref()
@@ -195,12 +577,6 @@ fn get_table_from_loaded_file(
         .as_table()
         .ok_or_else(|| SuperTomlError::InvalidTableType("root".to_string()))?;
 
-    let table = root_table
-        .get(table_name)
-        .ok_or_else(|| SuperTomlError::TableNotFound(table_name.to_string()))?;
-
-    table
-        .as_table()
-        .cloned()
-        .ok_or_else(|| SuperTomlError::InvalidTableType(table_name.to_string()))
+    let (table, table_path) = find_table(root_table, table_name)?;
+    Ok((table_path, table.clone()))
 }
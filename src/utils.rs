@@ -27,6 +27,73 @@ pub fn toml_value_to_jinja(value: &toml::Value) -> JinjaValue {
     }
 }
 
+/// Convert a Jinja value produced by an expression back into a TOML value
+///
+/// Used by typed template rendering so that `"{{ port }}"` keeps its native
+/// type. Values with no TOML equivalent (none, undefined) are rejected.
+pub fn jinja_value_to_toml(value: &JinjaValue) -> Result<toml::Value, String> {
+    let json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    json_value_to_toml(&json)
+}
+
+/// Convert a JSON value into a TOML value
+///
+/// Nulls cannot be represented in TOML and produce an error naming the
+/// offending location.
+pub fn json_value_to_toml(value: &serde_json::Value) -> Result<toml::Value, String> {
+    json_value_to_toml_at(value, "")
+}
+
+fn json_value_to_toml_at(value: &serde_json::Value, path: &str) -> Result<toml::Value, String> {
+    let location = || {
+        if path.is_empty() {
+            "value".to_string()
+        } else {
+            format!("'{}'", path)
+        }
+    };
+
+    match value {
+        serde_json::Value::Null => Err(format!(
+            "{} is null, which TOML cannot represent",
+            location()
+        )),
+        serde_json::Value::Bool(b) => Ok(toml::Value::Boolean(*b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(toml::Value::Integer(i))
+            } else if n.is_u64() {
+                Err(format!(
+                    "{} ({}) is too large for a TOML integer",
+                    location(),
+                    n
+                ))
+            } else {
+                Ok(toml::Value::Float(n.as_f64().unwrap_or_default()))
+            }
+        }
+        serde_json::Value::String(s) => Ok(toml::Value::String(s.clone())),
+        serde_json::Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, item)| json_value_to_toml_at(item, &format!("{}[{}]", path, i)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(toml::Value::Array),
+        serde_json::Value::Object(map) => {
+            let mut table = toml::map::Map::new();
+            for (k, v) in map {
+                let child_path = if path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", path, k)
+                };
+                table.insert(k.clone(), json_value_to_toml_at(v, &child_path)?);
+            }
+            Ok(toml::Value::Table(table))
+        }
+    }
+}
+
 /// Add values from table_values to resolver.values
 ///
 /// This is a common pattern used by most plugins to propagate their
@@ -36,7 +103,7 @@ pub fn add_values_to_resolver(
     table_values: &HashMap<String, toml::Value>,
 ) {
     for (key, value) in table_values {
-        resolver.values.insert(key.clone(), value.clone());
+        resolver.set_value(key, value.clone());
     }
 }
 
@@ -63,6 +130,11 @@ pub fn create_template_environment_with_meta(
         std::env::var(&name).unwrap_or(default)
     });
 
+    // Add Jinja2's `string` filter, used to force a string result in typed templates
+    env.add_filter("string", |value: JinjaValue| -> String {
+        value.to_string()
+    });
+
     // Note: _ object will be added to template context during processing
 
     env
@@ -128,6 +200,37 @@ mod tests {
         assert!(!_str_repr.is_empty());
     }
 
+    #[test]
+    fn test_jinja_value_to_toml_preserves_types() {
+        assert_eq!(
+            jinja_value_to_toml(&JinjaValue::from(5432)).unwrap(),
+            Value::Integer(5432)
+        );
+        assert_eq!(
+            jinja_value_to_toml(&JinjaValue::from(1.5)).unwrap(),
+            Value::Float(1.5)
+        );
+        assert_eq!(
+            jinja_value_to_toml(&JinjaValue::from(true)).unwrap(),
+            Value::Boolean(true)
+        );
+
+        let table = Value::Table(toml::toml! {
+            hosts = ["a", "b"]
+            port = 80
+        });
+        assert_eq!(
+            jinja_value_to_toml(&toml_value_to_jinja(&table)).unwrap(),
+            table
+        );
+    }
+
+    #[test]
+    fn test_jinja_value_to_toml_rejects_none() {
+        let error = jinja_value_to_toml(&JinjaValue::from(())).unwrap_err();
+        assert!(error.contains("null"));
+    }
+
     #[test]
     fn test_custom_env_or_function() {
         let env = create_template_environment_with_meta(HashMap::new());
@@ -0,0 +1,40 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+trap 'rm -f error.txt values.env' EXIT
+
+# Values python-dotenv would expand or read differently from docker compose
+# are errors
+if supertoml config.toml braces -o dotenv 2> error.txt; then
+    exit 1
+fi
+grep -q "value of 'value' cannot be written to a dotenv file: python-dotenv would expand" error.txt
+
+if supertoml config.toml dollar_backslash -o dotenv 2> error.txt; then
+    exit 1
+fi
+grep -q "docker compose and python-dotenv read differently" error.txt
+
+# Every other value reads back unchanged with python-dotenv, when available
+supertoml config.toml values -o dotenv > values.env
+if ! python3 -c "import dotenv" 2> /dev/null; then
+    echo "python-dotenv is not installed; skipping the round trip"
+    exit 0
+fi
+python3 - <<'PYTHON'
+import tomllib
+
+from dotenv import dotenv_values
+
+with open("config.toml", "rb") as f:
+    expected = tomllib.load(f)["values"]
+actual = dotenv_values("values.env")
+assert actual == expected, (actual, expected)
+PYTHON
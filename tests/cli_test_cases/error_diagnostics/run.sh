@@ -0,0 +1,45 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+trap 'rm -f error.txt' EXIT
+
+# Template errors point at the key in the table being resolved
+if supertoml config.toml prod 2> error.txt; then
+    exit 1
+fi
+expected='Error: Plugin '"'"'templating'"'"' error: Render error: unknown filter: filter nosuchfilter is unknown (in <string>:1)
+  --> config.toml:11:1
+   |
+11 | url = "https://{{ port | nosuchfilter }}"
+   | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+  = key: url
+  = while resolving: prod -> final'
+[ "$(cat error.txt)" = "$expected" ]
+
+# The same information is available as JSON
+if supertoml config.toml prod --error-format json 2> error.txt; then
+    exit 1
+fi
+grep -q '"call_stack":\["prod","final"\]' error.txt
+grep -q '"key":"url"' error.txt
+grep -q '"line":11' error.txt
+grep -q '"span":{"end":142,"start":101}' error.txt
+
+# TOML syntax errors are located too
+if supertoml invalid.toml a 2> error.txt; then
+    exit 1
+fi
+grep -q -- '--> invalid.toml:2:5' error.txt
+
+# --error-format applies to subcommands
+if supertoml explain invalid.toml a --error-format json 2> error.txt; then
+    exit 1
+fi
+grep -q '"file":"invalid.toml"' error.txt
@@ -0,0 +1,38 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# Values are passed unquoted, using the dotenv conversion rules
+[ "$(supertoml exec conf/config.toml app -- printenv name)" = "my app" ]
+[ "$(supertoml exec conf/config.toml app -- printenv port)" = "8080" ]
+[ "$(supertoml exec conf/config.toml app -- printenv tags)" = '["a","b"]' ]
+[ "$(supertoml exec conf/config.toml app -- printenv quote)" = "it's \"quoted\" \$HOME" ]
+
+# The command runs in the current directory, not the file's directory
+[ "$(supertoml exec conf/config.toml app -- pwd)" = "$(pwd)" ]
+
+# The parent environment is inherited unless --clean-env is given
+export SUPERTOML_EXEC_TEST=inherited
+[ "$(supertoml exec conf/config.toml app -- printenv SUPERTOML_EXEC_TEST)" = "inherited" ]
+[ "$(supertoml exec conf/config.toml app --clean-env -- /usr/bin/env | sort | tr '\n' ' ')" = "name=my app port=8080 quote=it's \"quoted\" \$HOME tags=[\"a\",\"b\"] " ]
+
+# Prefixes apply to every variable
+[ "$(supertoml exec conf/config.toml app --prefix APP_ -- printenv APP_port)" = "8080" ]
+
+# Command line values are included
+[ "$(supertoml exec conf/config.toml app --set port=9090 -- printenv port)" = "9090" ]
+
+# The child's exit code is propagated
+status=0
+supertoml exec conf/config.toml app -- sh -c 'exit 42' || status=$?
+[ "$status" = "42" ]
+
+status=0
+supertoml exec conf/config.toml app -- no-such-command-supertoml 2> /dev/null || status=$?
+[ "$status" = "127" ]
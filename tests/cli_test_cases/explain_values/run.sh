@@ -0,0 +1,64 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+expected='port = 443
+  from prod in config.toml
+  shadowed
+    80 from global in config.toml via _.before'
+
+[ "$(supertoml explain config.toml prod --key port)" = "$expected" ]
+
+# Values replaced by imports and _.after tables are listed, most recent first
+expected='region = "eu"
+  from final in config.toml via _.after
+  shadowed
+    "imported" from tools in tools.toml via _.import
+    "us" from global in config.toml via _.before'
+
+[ "$(supertoml explain config.toml prod --key region)" = "$expected" ]
+
+# Templated values show the value as written
+expected='url = "https://imported:443"
+  from prod in config.toml
+  raw "https://{{ region }}:{{ port }}"'
+
+[ "$(supertoml explain config.toml prod --key url)" = "$expected" ]
+
+# Command line overrides are reported as the final source
+output=$(supertoml explain config.toml prod --key port --set port=1)
+grep -q 'from command line via --set' <<< "$output"
+
+# Without a key every resolved value is explained
+[ "$(supertoml explain config.toml prod | grep -c ' = ')" = "4" ]
+
+# JSON output
+output=$(supertoml explain config.toml prod --key port --report json)
+grep -q '"table": "global"' <<< "$output"
+
+# Several tables are resolved as by extraction, later tables winning
+expected='port = 8080
+  from local in config.toml
+  shadowed
+    443 from prod in config.toml
+    80 from global in config.toml via _.before'
+
+[ "$(supertoml explain config.toml prod local --key port)" = "$expected" ]
+
+# Values are resolved for the output format
+expected='format = "json"
+  from local in config.toml
+  raw "{{ _.args.output_format }}"'
+
+[ "$(supertoml explain config.toml local --key format -o json)" = "$expected" ]
+
+# Unknown keys are reported
+if supertoml explain config.toml prod --key missing 2> /dev/null; then
+    exit 1
+fi
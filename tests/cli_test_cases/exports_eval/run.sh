@@ -0,0 +1,21 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# Exports output must be safe to eval and reproduce every value exactly
+rm -f injected.txt
+eval "$(supertoml config.toml test --output exports)"
+
+[ ! -e injected.txt ]
+[ "$command" = 'echo INJECTED; touch injected.txt' ]
+[ "$backticks" = '`touch injected.txt`' ]
+[ "$dollar" = '$HOME and ${PATH}' ]
+[ "$quotes" = "it's a \"test\"" ]
+[ "$multiline" = $'first line\nsecond line' ]
+[ "$trailing_backslash" = 'ends with \' ]
@@ -0,0 +1,28 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+trap 'rm -f error.txt' EXIT
+
+# Commands only run with --allow-exec
+if supertoml conf/config.toml app 2> error.txt; then
+    exit 1
+fi
+grep -q "pass --allow-exec to enable it" error.txt
+
+# Commands run in the importing file's directory and their output is parsed
+output=$(supertoml conf/config.toml app -o json --allow-exec)
+grep -q '"tf_vpc_id": "vpc-123"' <<< "$output"
+grep -q '"revision": "abc123"' <<< "$output"
+
+# Failing commands report their exit code and stderr
+if supertoml conf/config.toml failing --allow-exec 2> error.txt; then
+    exit 1
+fi
+grep -q "Command 'sh -c echo 'no state file' >&2; exit 3' failed with exit code 3: no state file" error.txt
@@ -0,0 +1,33 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+export SUPERTOML_TEST_APP_HOST=localhost
+export SUPERTOML_TEST_APP_PORT=8080
+export SUPERTOML_TEST_APP_DEBUG=true
+export SUPERTOML_TEST_APP_VERSION=1.0.0
+
+# Matching variables are imported with type inference
+output=$(supertoml config.toml app -o json)
+grep -q '"host": "localhost"' <<< "$output"
+grep -q '"port": 8080' <<< "$output"
+grep -q '"debug": true' <<< "$output"
+grep -q '"version": "1.0.0"' <<< "$output"
+grep -q '"url": "http://localhost:8080"' <<< "$output"
+
+# Without options names are kept and values stay strings
+output=$(supertoml config.toml raw -o json)
+grep -q '"SUPERTOML_TEST_APP_PORT": "8080"' <<< "$output"
+if grep -q HOST <<< "$output"; then
+    exit 1
+fi
+
+# explain shows the variable each value came from
+output=$(supertoml explain config.toml app --key port)
+grep -q 'from \$SUPERTOML_TEST_APP_PORT via _.import' <<< "$output"
@@ -0,0 +1,31 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# JSON and YAML formats come from the extension, INI from `format`
+output=$(supertoml config.toml app -o json)
+grep -q '"pkg_port": 3000' <<< "$output"
+grep -q '"pkg_debug": false' <<< "$output"
+grep -q '"b.example.com"' <<< "$output"
+grep -q '"image": "registry.example.com/web:1.4.2"' <<< "$output"
+grep -q '"image_pullPolicy": "IfNotPresent"' <<< "$output"
+grep -q '"db_host": "db.example.com"' <<< "$output"
+grep -q '"db_name": "app"' <<< "$output"
+
+# Without a table the whole file is imported
+grep -q '"log_level": "debug"' <<< "$output"
+grep -q '"greeting": "hello world"' <<< "$output"
+
+# Values TOML cannot represent are reported with their location
+if supertoml config.toml nulls 2> error.txt; then
+    exit 1
+fi
+trap 'rm -f error.txt' EXIT
+grep -q "'config.token' is null, which TOML cannot represent" error.txt
+grep -q "nulls.json" error.txt
@@ -0,0 +1,65 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+alias supertoml="${SUPERTOML_BIN}"
+
+repo=$(mktemp -d)
+trap 'rm -rf "$repo"' EXIT
+
+git -C "$repo" init -q
+git -C "$repo" config user.email "test@example.com"
+git -C "$repo" config user.name "test"
+mkdir -p "$repo/conf"
+
+cat > "$repo/conf/infra.toml" <<'TOML'
+[prod]
+replicas = 3
+region = "eu-west-1"
+TOML
+git -C "$repo" add conf/infra.toml
+git -C "$repo" commit -q -m "initial"
+git -C "$repo" tag v1
+
+# The working tree has moved on since the tag
+cat > "$repo/conf/infra.toml" <<'TOML'
+[prod]
+replicas = 5
+region = "eu-west-1"
+TOML
+
+cat > "$repo/conf/config.toml" <<'TOML'
+[released]
+_.import = [{ file = "infra.toml", table = "prod", git_ref = "v1" }]
+
+[resolved]
+_.import = [{ file = "infra.toml", table = "prod", git_ref = "v1", resolve = true }]
+
+[current]
+_.import = [{ file = "infra.toml", table = "prod" }]
+
+[missing]
+_.import = [{ file = "other.toml", git_ref = "v1" }]
+TOML
+
+# Values come from the file as it was at the revision
+output=$(supertoml "$repo/conf/config.toml" released -o json)
+grep -q '"replicas": 3' <<< "$output"
+
+output=$(supertoml "$repo/conf/config.toml" resolved -o json)
+grep -q '"replicas": 3' <<< "$output"
+
+output=$(supertoml "$repo/conf/config.toml" current -o json)
+grep -q '"replicas": 5' <<< "$output"
+
+# Provenance names the revision
+output=$(supertoml explain "$repo/conf/config.toml" released --key replicas)
+grep -q "infra.toml@v1" <<< "$output"
+
+# Files missing at the revision are errors
+if supertoml "$repo/conf/config.toml" missing 2> "$repo/error.txt"; then
+    exit 1
+fi
+grep -q "Command 'git cat-file blob v1:./other.toml' failed" "$repo/error.txt"
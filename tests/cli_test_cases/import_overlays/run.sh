@@ -0,0 +1,27 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# Glob matches are imported in sorted order, later files winning
+output=$(supertoml config.toml app -o json)
+grep -q '"port": 80' <<< "$output"
+grep -q '"log_level": "debug"' <<< "$output"
+
+# Missing optional files are skipped and noted by explain
+output=$(supertoml explain config.toml app)
+grep -q "log_level = \"debug\"" <<< "$output"
+grep -q "from app in conf.d/20-debug.toml via _.import" <<< "$output"
+grep -q "note: skipped optional import 'local.toml': not found" <<< "$output"
+
+# Missing files are an error unless optional
+if supertoml config.toml strict 2> error.txt; then
+    exit 1
+fi
+trap 'rm -f error.txt' EXIT
+grep -q "Failed to read file" error.txt
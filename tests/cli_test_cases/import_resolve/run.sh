@@ -0,0 +1,27 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# The imported table runs its own directives and templates, with its imports
+# relative to the imported file
+output=$(supertoml conf/app.toml app -o json)
+grep -q '"db_host": "orders.db.example.com"' <<< "$output"
+grep -q '"db_port": 5432' <<< "$output"
+grep -q '"url": "postgres://orders.db.example.com:5432/orders"' <<< "$output"
+
+# Without resolve the values are templated in the importing table instead
+output=$(supertoml conf/app.toml raw -o json)
+grep -q '"host": ".db.example.com"' <<< "$output"
+
+# Imports that lead back to a table being resolved are cycles
+if supertoml conf/app.toml loop 2> error.txt; then
+    exit 1
+fi
+trap 'rm -f error.txt' EXIT
+grep -q "Cycle detected when processing table 'loop': loop -> conf/shared/db.toml \[loop\] (_.import) -> loop (_.import)" error.txt
@@ -0,0 +1,27 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+expected="final
+global
+services
+services.api
+  _.before: global, services.shared
+  _.import: tools.toml [tools]
+  _.after: final
+services.shared"
+
+[ "$(supertoml list config.toml)" = "$expected" ]
+
+# JSON output lists the same tables and dependencies
+supertoml list config.toml --output json > list.json
+trap 'rm -f list.json' EXIT
+grep -q '"table": "services.api"' list.json
+grep -q '"file": "tools.toml"' list.json
+grep -q '"directive": "after"' list.json
@@ -0,0 +1,31 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+trap 'rm -rf conf/out' EXIT
+rm -rf conf/out
+
+# Every output is written, with paths relative to the file
+supertoml render conf/config.toml > /dev/null
+
+[ "$(cat conf/out/.env)" = "$(printf 'name=myapp\nport=8080')" ]
+grep -q '"port": 443' conf/out/config.json
+grep -q '"format": "json"' conf/out/config.json
+[ "$(cat conf/out/terraform.tfvars)" = 'region = "eu-west-1"' ]
+
+# Command line values apply to every output
+supertoml render conf/config.toml --set port=1 > /dev/null
+[ "$(grep port conf/out/.env)" = "port=1" ]
+
+# A failing output leaves every file untouched
+if supertoml render conf/broken.toml 2> /dev/null; then
+    exit 1
+fi
+[ "$(grep name conf/out/.env)" = "name=myapp" ]
+[ "$(ls -A conf/out | sort | tr '\n' ' ')" = ".env config.json terraform.tfvars " ]
@@ -0,0 +1,35 @@
+#!/bin/bash
+
+set -euo pipefail
+
+shopt -s expand_aliases
+
+cd "$(dirname "$0")"
+
+alias supertoml="${SUPERTOML_BIN}"
+
+# Overrides are parsed as TOML literals and replace resolved values
+output=$(supertoml conf/config.toml app -o json --set port=8080 --set debug=true --set 'version=1.0.0' --set db.host=localhost)
+grep -q '"port": 8080' <<< "$output"
+grep -q '"debug": true' <<< "$output"
+grep -q '"version": "1.0.0"' <<< "$output"
+grep -q '"host": "localhost"' <<< "$output"
+
+# In the default mode templates do not see overrides
+[ "$(supertoml conf/config.toml app -o dotenv --set user=bob | grep greeting)" = "greeting='hello '" ]
+
+# Seed values are visible to templates
+[ "$(supertoml conf/config.toml app -o dotenv --set user=bob --set-mode seed | grep greeting)" = "greeting='hello bob'" ]
+
+# Seed values are replaced by the table's own values
+[ "$(supertoml conf/config.toml app -o dotenv --set port=1 --set-mode seed | grep port)" = "port=80" ]
+
+# --set-file paths are relative to the working directory, not the config file
+[ "$(supertoml conf/config.toml app -o json --set-file motd=motd.txt | grep motd)" = '  "motd": "line one\nline two\n",' ]
+
+# Malformed overrides are reported
+if supertoml conf/config.toml app --set port 2> error.txt; then
+    exit 1
+fi
+trap 'rm -f error.txt' EXIT
+grep -q "Invalid override 'port': expected key=value" error.txt
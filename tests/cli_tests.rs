@@ -23,7 +23,7 @@ fn run_cli_test_case(test_dir: &PathBuf) -> Result<(), String> {
     let script_path = test_dir.join("run.sh");
 
     if !script_path.exists() {
-        return Err(format!("run.sh not found"));
+        return Err("run.sh not found".to_string());
     }
 
     let absolute_script_path = script_path
@@ -34,7 +34,7 @@ fn run_cli_test_case(test_dir: &PathBuf) -> Result<(), String> {
 
     let output = Command::new("bash")
         .arg(&absolute_script_path)
-        .current_dir(&test_dir)
+        .current_dir(test_dir)
         .env("SUPERTOML_BIN", &supertoml_path)
         .output()
         .map_err(|e| format!("Failed to execute run.sh: {}", e))?;
@@ -47,7 +47,7 @@ fn run_cli_test_case(test_dir: &PathBuf) -> Result<(), String> {
     }
 
     if !output.status.success() {
-        return Err(format!("Script exited with non-zero status"));
+        return Err("Script exited with non-zero status".to_string());
     }
 
     Ok(())
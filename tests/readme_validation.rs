@@ -65,8 +65,9 @@ fn get_resolved_values_for_testing() -> std::collections::HashMap<String, toml::
     let toml_example =
         extract_toml_example(&readme_content).expect("Failed to extract TOML example from README");
 
-    // Create a temporary file with the TOML content
-    let temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+    // Create a temporary file with the TOML content next to the README, since
+    // its import paths are relative to the file
+    let temp_file = NamedTempFile::new_in(".").expect("Failed to create temporary file");
 
     fs::write(&temp_file, toml_example).expect("Failed to write TOML to temporary file");
 
@@ -6,11 +6,13 @@ use supertoml::SuperTomlError;
 struct TestCase {
     name: String,
     description: String,
-    table: String,
+    tables: Vec<String>,
     expected_toml: Option<String>,
     expected_json: Option<String>,
     expected_dotenv: Option<String>,
     expected_exports: Option<String>,
+    expected_tfvars: Option<String>,
+    expected_yaml: Option<String>,
     expected_error: Option<String>,
 }
 
@@ -36,11 +38,15 @@ fn load_test_case(test_file: &str) -> Result<TestCase, SuperTomlError> {
         .and_then(|v| v.as_str())
         .ok_or_else(|| SuperTomlError::TableNotFound("description".to_string()))?
         .to_string();
-    let table = test_table
-        .get("table")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| SuperTomlError::TableNotFound("table".to_string()))?
-        .to_string();
+    // `table` is either a single table name or an ordered list of them
+    let tables = match test_table.get("table") {
+        Some(toml::Value::String(table)) => vec![table.clone()],
+        Some(toml::Value::Array(tables)) => tables
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => return Err(SuperTomlError::TableNotFound("table".to_string())),
+    };
 
     let get_expected_content = |format: &str| -> Option<String> {
         let expected_table = root_table.get("expected")?.as_table()?;
@@ -61,23 +67,25 @@ fn load_test_case(test_file: &str) -> Result<TestCase, SuperTomlError> {
     Ok(TestCase {
         name,
         description,
-        table,
+        tables,
         expected_toml: get_expected_content("toml"),
         expected_json: get_expected_content("json"),
         expected_dotenv: get_expected_content("dotenv"),
         expected_exports: get_expected_content("exports"),
+        expected_tfvars: get_expected_content("tfvars"),
+        expected_yaml: get_expected_content("yaml"),
         expected_error,
     })
 }
 
-fn run_supertoml_cli(test_file: &str, table: &str, format: &str) -> Result<String, String> {
+fn run_supertoml_cli(test_file: &str, tables: &[String], format: &str) -> Result<String, String> {
     // Use Cargo's built-in mechanism to find the binary under test
     // CARGO_BIN_EXE_supertoml is set by Cargo when running integration tests
     let supertoml_bin = env!("CARGO_BIN_EXE_supertoml");
 
     let output = Command::new(supertoml_bin)
         .arg(test_file)
-        .arg(table)
+        .args(tables)
         .arg("--output")
         .arg(format)
         .output()
@@ -103,7 +111,7 @@ fn run_test_file(test_file: &str) {
 
     if let Some(expected_error) = &test_case.expected_error {
         // Test error cases by running supertoml and checking stderr
-        let result = run_supertoml_cli(test_file, &test_case.table, "toml");
+        let result = run_supertoml_cli(test_file, &test_case.tables, "toml");
         match result {
             Ok(_) => panic!(
                 "Expected error matching '{}' but got success",
@@ -124,10 +132,14 @@ fn run_test_file(test_file: &str) {
     } else {
         // Test successful cases by running supertoml for each expected format
         if let Some(expected) = test_case.expected_toml {
-            let actual = run_supertoml_cli(test_file, &test_case.table, "toml").expect(&format!(
-                "Failed to resolve table '{}' from {}",
-                test_case.table, test_file
-            ));
+            let actual =
+                run_supertoml_cli(test_file, &test_case.tables, "toml").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
             assert_eq!(
                 actual.trim(),
                 expected,
@@ -137,10 +149,14 @@ fn run_test_file(test_file: &str) {
         }
 
         if let Some(expected) = test_case.expected_json {
-            let actual = run_supertoml_cli(test_file, &test_case.table, "json").expect(&format!(
-                "Failed to resolve table '{}' from {}",
-                test_case.table, test_file
-            ));
+            let actual =
+                run_supertoml_cli(test_file, &test_case.tables, "json").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
             assert_eq!(
                 actual.trim(),
                 expected,
@@ -150,10 +166,14 @@ fn run_test_file(test_file: &str) {
         }
 
         if let Some(expected) = test_case.expected_dotenv {
-            let actual = run_supertoml_cli(test_file, &test_case.table, "dotenv").expect(&format!(
-                "Failed to resolve table '{}' from {}",
-                test_case.table, test_file
-            ));
+            let actual =
+                run_supertoml_cli(test_file, &test_case.tables, "dotenv").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
             assert_eq!(
                 actual.trim(),
                 expected,
@@ -164,10 +184,13 @@ fn run_test_file(test_file: &str) {
 
         if let Some(expected) = test_case.expected_exports {
             let actual =
-                run_supertoml_cli(test_file, &test_case.table, "exports").expect(&format!(
-                    "Failed to resolve table '{}' from {}",
-                    test_case.table, test_file
-                ));
+                run_supertoml_cli(test_file, &test_case.tables, "exports").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
             assert_eq!(
                 actual.trim(),
                 expected,
@@ -175,6 +198,40 @@ fn run_test_file(test_file: &str) {
                 test_file
             );
         }
+
+        if let Some(expected) = test_case.expected_tfvars {
+            let actual =
+                run_supertoml_cli(test_file, &test_case.tables, "tfvars").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
+            assert_eq!(
+                actual.trim(),
+                expected,
+                "Tfvars output mismatch in {}",
+                test_file
+            );
+        }
+
+        if let Some(expected) = test_case.expected_yaml {
+            let actual =
+                run_supertoml_cli(test_file, &test_case.tables, "yaml").unwrap_or_else(|_| {
+                    panic!(
+                        "Failed to resolve table '{}' from {}",
+                        test_case.tables.join(", "),
+                        test_file
+                    )
+                });
+            assert_eq!(
+                actual.trim(),
+                expected,
+                "YAML output mismatch in {}",
+                test_file
+            );
+        }
     }
 }
 